@@ -12,6 +12,8 @@
         "bindings/node/binding.cc",
         "src/parser.c",
         "src/scanner.c",
+        "osh/src/parser.c",
+        "osh/src/scanner.c",
       ],
       "cflags_c": [
         "-std=c11",
@@ -3,6 +3,7 @@
 typedef struct TSLanguage TSLanguage;
 
 extern "C" TSLanguage *tree_sitter_ysh();
+extern "C" TSLanguage *tree_sitter_osh();
 
 // "tree-sitter", "currentLanguage" hance the readability of types in
 // the generated code.
@@ -10,6 +11,12 @@ Napi::Object Init(Napi::Env env, Napi::Object exports) {
   exports["name"] = Napi::String::New(env, "ysh");
   auto language = Napi::External<TSLanguage>::New(env, tree_sitter_ysh());
   exports["language"] = language;
+
+  // OSH, the bash-compatible dialect, whose grammar extends YSH
+  auto osh = Napi::Object::New(env);
+  osh["name"] = Napi::String::New(env, "osh");
+  osh["language"] = Napi::External<TSLanguage>::New(env, tree_sitter_osh());
+  exports["osh"] = osh;
   return exports;
 }
 
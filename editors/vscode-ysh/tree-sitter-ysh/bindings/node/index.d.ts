@@ -24,6 +24,8 @@ type Language = {
   nodeTypeInfo: NodeInfo[];
 };
 
-declare const language: Language;
+declare const language: Language & {
+  osh: Language;
+};
 export = language;
 
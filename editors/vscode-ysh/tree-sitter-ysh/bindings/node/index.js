@@ -4,5 +4,6 @@ module.exports = require("node-gyp-build")(root);
 
 try {
   module.exports.nodeTypeInfo = require("../../src/node-types.json");
+  module.exports.osh.nodeTypeInfo = require("../../osh/src/node-types.json");
 } catch (_) {}
 
@@ -0,0 +1,53 @@
+//! Typed wrappers around YSH syntax nodes.
+//!
+//! There is one struct per named node kind in the grammar, generated at build
+//! time from `src/node-types.json`, with an accessor per field and one for the
+//! remaining named children. Use [`AstNode::cast`] to go from a
+//! [`tree_sitter::Node`] to its wrapper, and [`AnyNode`] when the kind isn't
+//! known in advance.
+//!
+//! ```
+//! use tree_sitter_ysh::ast::{AstNode, SourceFile, VarDeclaration};
+//!
+//! let mut parser = tree_sitter::Parser::new();
+//! parser.set_language(&tree_sitter_ysh::language()).unwrap();
+//! let tree = parser.parse("var x = 42", None).unwrap();
+//!
+//! let file = SourceFile::cast(tree.root_node()).unwrap();
+//! let decl = VarDeclaration::cast(file.node().named_child(0).unwrap());
+//! assert!(decl.is_some());
+//! ```
+
+use tree_sitter::Node;
+
+/// A typed view of a [`Node`] of one or more known kinds.
+pub trait AstNode<'tree>: Sized {
+    /// Whether nodes of this kind can be wrapped by this type.
+    fn can_cast(kind: &str) -> bool;
+
+    /// Wraps `node`, or returns `None` if it has a different kind.
+    fn cast(node: Node<'tree>) -> Option<Self>;
+
+    /// The underlying syntax node.
+    fn node(&self) -> Node<'tree>;
+}
+
+/// Named, non-extra children of `node` that are not assigned to a field.
+fn unnamed_children(node: Node<'_>) -> Vec<Node<'_>> {
+    let mut cursor = node.walk();
+    let mut children = Vec::new();
+    if cursor.goto_first_child() {
+        loop {
+            let child = cursor.node();
+            if cursor.field_name().is_none() && child.is_named() && !child.is_extra() {
+                children.push(child);
+            }
+            if !cursor.goto_next_sibling() {
+                break;
+            }
+        }
+    }
+    children
+}
+
+include!(concat!(env!("OUT_DIR"), "/ast.rs"));
@@ -0,0 +1,276 @@
+//! Generates the typed AST wrappers in `ast.rs` from `src/node-types.json`.
+//!
+//! This is a module of the build script, not of the library.
+
+use std::fmt::Write;
+
+use serde_json::Value;
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "yield",
+];
+
+/// Accessor names used by the wrappers themselves.
+const RESERVED_METHODS: &[&str] = &["cast", "can_cast", "node", "children", "child"];
+
+struct NodeType {
+    kind: String,
+    fields: Vec<(String, ChildInfo)>,
+    children: Option<ChildInfo>,
+    subtypes: Vec<String>,
+}
+
+struct ChildInfo {
+    multiple: bool,
+    types: Vec<(String, bool)>,
+}
+
+impl ChildInfo {
+    fn from_json(value: &Value) -> ChildInfo {
+        let types = value["types"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| {
+                (
+                    t["type"].as_str().unwrap().to_string(),
+                    t["named"].as_bool().unwrap(),
+                )
+            })
+            .collect();
+        ChildInfo {
+            multiple: value["multiple"].as_bool().unwrap(),
+            types,
+        }
+    }
+
+    /// The Rust type of one child, and how to convert a raw `Node` into it.
+    fn rust_type(&self) -> (String, &'static str) {
+        let named: Vec<_> = self.types.iter().filter(|(_, named)| *named).collect();
+        if named.len() != self.types.len() {
+            // Anonymous tokens like operators have no wrapper.
+            ("Node<'tree>".to_string(), "Some")
+        } else if named.len() == 1 {
+            (format!("{}<'tree>", type_name(&named[0].0)), "cast")
+        } else {
+            ("AnyNode<'tree>".to_string(), "cast")
+        }
+    }
+}
+
+pub fn generate(node_types_json: &str) -> String {
+    let json: Value = serde_json::from_str(node_types_json).expect("invalid node-types.json");
+    let node_types: Vec<NodeType> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|n| n["named"].as_bool().unwrap())
+        .map(|n| NodeType {
+            kind: n["type"].as_str().unwrap().to_string(),
+            fields: n["fields"]
+                .as_object()
+                .map(|fields| {
+                    fields
+                        .iter()
+                        .map(|(name, info)| (name.clone(), ChildInfo::from_json(info)))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            children: n.get("children").map(ChildInfo::from_json),
+            subtypes: n
+                .get("subtypes")
+                .and_then(Value::as_array)
+                .map(|subtypes| {
+                    subtypes
+                        .iter()
+                        .map(|t| t["type"].as_str().unwrap().to_string())
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+        .collect();
+
+    let mut out = String::new();
+    out.push_str("// Generated by build.rs from src/node-types.json. Do not edit.\n\n");
+
+    for node_type in &node_types {
+        if node_type.subtypes.is_empty() {
+            write_struct(&mut out, node_type);
+        } else {
+            write_enum(
+                &mut out,
+                &type_name(&node_type.kind),
+                &format!("A `{}` node.", node_type.kind),
+                &node_type.subtypes,
+            );
+        }
+    }
+
+    let all_kinds: Vec<String> = node_types
+        .iter()
+        .filter(|n| n.subtypes.is_empty())
+        .map(|n| n.kind.clone())
+        .collect();
+    write_enum(&mut out, "AnyNode", "Any named YSH node.", &all_kinds);
+
+    out
+}
+
+fn write_struct(out: &mut String, node_type: &NodeType) {
+    let name = type_name(&node_type.kind);
+    let kind = &node_type.kind;
+
+    writeln!(out, "/// A `{kind}` node.").unwrap();
+    writeln!(out, "#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]").unwrap();
+    writeln!(out, "pub struct {name}<'tree>(Node<'tree>);\n").unwrap();
+
+    writeln!(out, "impl<'tree> AstNode<'tree> for {name}<'tree> {{").unwrap();
+    writeln!(out, "    fn can_cast(kind: &str) -> bool {{").unwrap();
+    writeln!(out, "        kind == {kind:?}").unwrap();
+    writeln!(out, "    }}\n").unwrap();
+    writeln!(out, "    fn cast(node: Node<'tree>) -> Option<Self> {{").unwrap();
+    writeln!(
+        out,
+        "        Self::can_cast(node.kind()).then_some(Self(node))"
+    )
+    .unwrap();
+    writeln!(out, "    }}\n").unwrap();
+    writeln!(out, "    fn node(&self) -> Node<'tree> {{").unwrap();
+    writeln!(out, "        self.0").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}\n").unwrap();
+
+    if node_type.fields.is_empty() && node_type.children.is_none() {
+        return;
+    }
+
+    writeln!(out, "impl<'tree> {name}<'tree> {{").unwrap();
+    for (field, info) in &node_type.fields {
+        let (ty, convert) = info.rust_type();
+        let method = method_name(field);
+        if info.multiple {
+            writeln!(out, "    /// The children in the `{field}` field.").unwrap();
+            writeln!(out, "    pub fn {method}(&self) -> Vec<{ty}> {{").unwrap();
+            writeln!(out, "        let mut cursor = self.0.walk();").unwrap();
+            writeln!(out, "        self.0").unwrap();
+            writeln!(
+                out,
+                "            .children_by_field_name({field:?}, &mut cursor)"
+            )
+            .unwrap();
+            write_filter(out, "filter_map", convert, &ty);
+            writeln!(out, "            .collect()").unwrap();
+        } else {
+            writeln!(out, "    /// The child in the `{field}` field.").unwrap();
+            writeln!(out, "    pub fn {method}(&self) -> Option<{ty}> {{").unwrap();
+            writeln!(out, "        self.0").unwrap();
+            writeln!(out, "            .child_by_field_name({field:?})").unwrap();
+            write_filter(out, "and_then", convert, &ty);
+        }
+        writeln!(out, "    }}\n").unwrap();
+    }
+    if let Some(info) = &node_type.children {
+        let (ty, convert) = info.rust_type();
+        if info.multiple {
+            writeln!(out, "    /// The named children that are not in a field.").unwrap();
+            writeln!(out, "    pub fn children(&self) -> Vec<{ty}> {{").unwrap();
+            writeln!(out, "        unnamed_children(self.0)").unwrap();
+            if convert != "Some" {
+                writeln!(out, "            .into_iter()").unwrap();
+                write_filter(out, "filter_map", convert, &ty);
+                writeln!(out, "            .collect()").unwrap();
+            }
+        } else {
+            writeln!(out, "    /// The named child that is not in a field.").unwrap();
+            writeln!(out, "    pub fn child(&self) -> Option<{ty}> {{").unwrap();
+            writeln!(out, "        unnamed_children(self.0)").unwrap();
+            writeln!(out, "            .into_iter()").unwrap();
+            if convert == "Some" {
+                writeln!(out, "            .next()").unwrap();
+            } else {
+                write_filter(out, "find_map", convert, &ty);
+            }
+        }
+        writeln!(out, "    }}\n").unwrap();
+    }
+    // Drop the blank line after the last method.
+    out.pop();
+    writeln!(out, "}}\n").unwrap();
+}
+
+fn write_enum(out: &mut String, name: &str, doc: &str, kinds: &[String]) {
+    writeln!(out, "/// {doc}").unwrap();
+    writeln!(out, "#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]").unwrap();
+    writeln!(out, "pub enum {name}<'tree> {{").unwrap();
+    for kind in kinds {
+        let variant = type_name(kind);
+        writeln!(out, "    {variant}({variant}<'tree>),").unwrap();
+    }
+    writeln!(out, "}}\n").unwrap();
+
+    writeln!(out, "impl<'tree> AstNode<'tree> for {name}<'tree> {{").unwrap();
+    writeln!(out, "    fn can_cast(kind: &str) -> bool {{").unwrap();
+    writeln!(out, "        matches!(").unwrap();
+    writeln!(out, "            kind,").unwrap();
+    for (i, kind) in kinds.iter().enumerate() {
+        let sep = if i == 0 { "  " } else { "| " };
+        writeln!(out, "            {sep}{kind:?}").unwrap();
+    }
+    writeln!(out, "        )").unwrap();
+    writeln!(out, "    }}\n").unwrap();
+    writeln!(out, "    fn cast(node: Node<'tree>) -> Option<Self> {{").unwrap();
+    writeln!(out, "        match node.kind() {{").unwrap();
+    for kind in kinds {
+        let variant = type_name(kind);
+        writeln!(
+            out,
+            "            {kind:?} => {variant}::cast(node).map(Self::{variant}),"
+        )
+        .unwrap();
+    }
+    writeln!(out, "            _ => None,").unwrap();
+    writeln!(out, "        }}").unwrap();
+    writeln!(out, "    }}\n").unwrap();
+    writeln!(out, "    fn node(&self) -> Node<'tree> {{").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    for kind in kinds {
+        let variant = type_name(kind);
+        writeln!(out, "            Self::{variant}(n) => n.node(),").unwrap();
+    }
+    writeln!(out, "        }}").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}\n").unwrap();
+}
+
+/// Emits `.{adapter}(Foo::cast)`, or nothing when children stay raw nodes.
+fn write_filter(out: &mut String, adapter: &str, convert: &str, ty: &str) {
+    if convert == "cast" {
+        let name = &ty[..ty.find('<').unwrap()];
+        writeln!(out, "            .{adapter}({name}::cast)").unwrap();
+    }
+}
+
+/// `proc_definition` -> `ProcDefinition`
+fn type_name(kind: &str) -> String {
+    kind.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first).chain(chars).collect::<String>()
+        })
+        .collect()
+}
+
+fn method_name(field: &str) -> String {
+    if RESERVED_METHODS.contains(&field) {
+        format!("{field}_field")
+    } else if RUST_KEYWORDS.contains(&field) {
+        format!("r#{field}")
+    } else {
+        field.to_string()
+    }
+}
@@ -1,21 +1,59 @@
+mod ast_gen;
+
 fn main() {
     let src_dir = std::path::Path::new("src");
 
-    let mut c_config = cc::Build::new();
-    c_config.include(src_dir);
-    c_config
-        .flag_if_supported("-Wno-unused-parameter")
-        .flag_if_supported("-Wno-unused-but-set-variable")
-        .flag_if_supported("-Wno-trigraphs");
+    // YSH, and OSH, whose grammar extends it
+    for (name, dir) in [("ysh", src_dir), ("osh", std::path::Path::new("osh/src"))] {
+        let mut c_config = cc::Build::new();
+        c_config.include(dir);
+        c_config
+            .flag_if_supported("-Wno-unused-parameter")
+            .flag_if_supported("-Wno-unused-but-set-variable")
+            .flag_if_supported("-Wno-trigraphs");
 
-    let parser_path = src_dir.join("parser.c");
-    c_config.file(&parser_path);
+        for file in ["parser.c", "scanner.c"] {
+            let path = dir.join(file);
+            c_config.file(&path);
+            println!("cargo:rerun-if-changed={}", path.to_str().unwrap());
+        }
 
-    let scanner_path = src_dir.join("scanner.c");
-    if scanner_path.exists() {
-        c_config.file(&scanner_path);
+        c_config.compile(&format!("tree-sitter-{name}"));
     }
+    println!("cargo:rerun-if-changed=common/scanner.h");
+
+    let parser_source = std::fs::read_to_string(src_dir.join("parser.c")).unwrap();
 
-    c_config.compile("tree-sitter-ysh");
+    let node_types_path = src_dir.join("node-types.json");
+    let node_types = std::fs::read_to_string(&node_types_path).unwrap();
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    std::fs::write(out_dir.join("ast.rs"), ast_gen::generate(&node_types)).unwrap();
+    std::fs::write(out_dir.join("fields.rs"), field_constants(&parser_source)).unwrap();
+    println!(
+        "cargo:rerun-if-changed={}",
+        node_types_path.to_str().unwrap()
+    );
+    println!("cargo:rerun-if-changed=bindings/rust/ast_gen.rs");
 }
 
+/// Turns the `ts_field_identifiers` enum in `parser.c` into `FIELD_*` constants.
+fn field_constants(parser_source: &str) -> String {
+    let start = parser_source
+        .find("enum ts_field_identifiers {")
+        .expect("parser.c has no field identifiers");
+    let end = start + parser_source[start..].find("};").unwrap();
+
+    let mut out = String::new();
+    for line in parser_source[start..end].lines().skip(1) {
+        let Some((name, id)) = line.trim().trim_end_matches(',').split_once(" = ") else {
+            continue;
+        };
+        let name = name.strip_prefix("field_").unwrap();
+        out.push_str(&format!(
+            "/// The id of the `{name}` field, for [`tree_sitter::Node::child_by_field_id`].\n\
+             pub const FIELD_{}: u16 = {id};\n",
+            name.to_uppercase()
+        ));
+    }
+    out
+}
@@ -0,0 +1,156 @@
+//! Deciding whether a script is YSH, OSH or bash, to pick a parser for it.
+//!
+//! ```
+//! use tree_sitter_ysh::{detect_dialect, Dialect};
+//!
+//! let source = b"#!/usr/bin/env osh\nshopt --set ysh:upgrade\nvar x = 1\n";
+//! assert_eq!(detect_dialect("bin/deploy", source), Dialect::Ysh);
+//! assert_eq!(detect_dialect("build.sh", b"make\n"), Dialect::Bash);
+//! ```
+
+use std::path::Path;
+
+use tree_sitter::Language;
+
+/// A shell language of the Oils project, or bash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Dialect {
+    /// YSH, or OSH with `shopt --set ysh:upgrade` or `ysh:all`.
+    Ysh,
+    /// OSH, the bash-compatible dialect, including bash scripts that set
+    /// OSH-only options like `strict:all`.
+    Osh,
+    /// Plain bash or POSIX sh.
+    Bash,
+}
+
+impl Dialect {
+    /// The tree-sitter [`Language`] to parse this dialect with. Bash is
+    /// parsed with the OSH grammar.
+    pub fn language(self) -> Language {
+        match self {
+            Dialect::Ysh => crate::language(),
+            Dialect::Osh | Dialect::Bash => crate::osh_language(),
+        }
+    }
+}
+
+/// Decide the dialect of the script at `path` with contents `source`.
+///
+/// The shebang line wins over the file extension, so extensionless scripts in
+/// `bin/` are detected too. A `shopt --set ysh:upgrade` or `ysh:all` line
+/// then makes the script YSH, and `strict:all` makes a bash script OSH, since
+/// bash has no such option. Scripts with neither a shebang nor a known
+/// extension are assumed to be bash.
+pub fn detect_dialect(path: impl AsRef<Path>, source: &[u8]) -> Dialect {
+    let source = String::from_utf8_lossy(source);
+    let mut dialect = shebang_dialect(&source)
+        .or_else(|| extension_dialect(path.as_ref()))
+        .unwrap_or(Dialect::Bash);
+
+    for option in shopt_options(&source) {
+        match option {
+            "ysh:upgrade" | "ysh:all" | "oil:upgrade" | "oil:all" => return Dialect::Ysh,
+            "strict:all" if dialect == Dialect::Bash => dialect = Dialect::Osh,
+            _ => {}
+        }
+    }
+    dialect
+}
+
+// `#!/bin/bash`, `#!/usr/bin/env ysh` or `#!/usr/bin/env -S osh -e`
+fn shebang_dialect(source: &str) -> Option<Dialect> {
+    let line = source.lines().next()?.strip_prefix("#!")?;
+    let mut words = line.split_whitespace();
+    let mut interpreter = program_name(words.next()?);
+    if interpreter == "env" {
+        interpreter = program_name(words.find(|word| !word.starts_with('-'))?);
+    }
+    dialect_named(interpreter)
+}
+
+fn extension_dialect(path: &Path) -> Option<Dialect> {
+    dialect_named(path.extension()?.to_str()?)
+}
+
+fn dialect_named(name: &str) -> Option<Dialect> {
+    match name {
+        "ysh" | "oil" => Some(Dialect::Ysh),
+        "osh" => Some(Dialect::Osh),
+        "bash" | "sh" => Some(Dialect::Bash),
+        _ => None,
+    }
+}
+
+fn program_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+// The options set by `shopt -s` or `shopt --set` lines, ignoring comments
+fn shopt_options(source: &str) -> impl Iterator<Item = &str> {
+    source.lines().flat_map(|line| {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut words = line
+            .split(|c: char| c.is_whitespace() || c == ';' || c == '{')
+            .filter(|word| !word.is_empty());
+        let is_shopt = words.next() == Some("shopt");
+
+        let mut setting = false;
+        words
+            .take_while(move |_| is_shopt)
+            .filter(move |&word| match word {
+                "-s" | "--set" => {
+                    setting = true;
+                    false
+                }
+                "-u" | "--unset" => {
+                    setting = false;
+                    false
+                }
+                _ => setting && !word.starts_with('-'),
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shebang_wins_over_extension() {
+        assert_eq!(
+            detect_dialect("bin/run", b"#!/usr/bin/env ysh\n"),
+            Dialect::Ysh
+        );
+        assert_eq!(
+            detect_dialect("bin/run", b"#!/usr/local/bin/osh\n"),
+            Dialect::Osh
+        );
+        assert_eq!(detect_dialect("run.ysh", b"#!/bin/bash\n"), Dialect::Bash);
+        assert_eq!(detect_dialect("run.osh", b"echo hi\n"), Dialect::Osh);
+        assert_eq!(detect_dialect("README", b"echo hi\n"), Dialect::Bash);
+    }
+
+    #[test]
+    fn test_shopt_directives() {
+        assert_eq!(
+            detect_dialect("run.osh", b"shopt -s ysh:all\n"),
+            Dialect::Ysh
+        );
+        assert_eq!(
+            detect_dialect("run.sh", b"set -e\nshopt --set strict:all  # safer\n"),
+            Dialect::Osh
+        );
+        assert_eq!(
+            detect_dialect("run.sh", b"shopt --unset ysh:upgrade\n"),
+            Dialect::Bash
+        );
+        assert_eq!(
+            detect_dialect(
+                "run.sh",
+                b"# shopt --set ysh:all\necho shopt --set ysh:all\n"
+            ),
+            Dialect::Bash
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! Docstrings of procs and funcs, written as `###` lines at the top of the body.
+//!
+//! ```
+//! let source = "proc greet (name) {\n  ### Print a greeting\n  echo hi $name\n}\n";
+//! let mut parser = tree_sitter::Parser::new();
+//! parser.set_language(&tree_sitter_ysh::language()).unwrap();
+//! let tree = parser.parse(source, None).unwrap();
+//!
+//! let proc_def = tree.root_node().named_child(0).unwrap();
+//! let doc = tree_sitter_ysh::docs::docstring(proc_def, source.as_bytes());
+//! assert_eq!(doc.as_deref(), Some("Print a greeting"));
+//! ```
+
+use tree_sitter::Node;
+
+/// The docstring of a `proc_definition` or `func_definition` node.
+///
+/// Each `###` line has its marker and one following space removed, and the
+/// lines are joined with `\n`. Returns `None` for other nodes and for bodies
+/// without a docstring.
+pub fn docstring(node: Node<'_>, source: &[u8]) -> Option<String> {
+    if !matches!(node.kind(), "proc_definition" | "func_definition") {
+        return None;
+    }
+    let body = node.child_by_field_name("body")?;
+
+    let mut cursor = body.walk();
+    let lines: Vec<&str> = body
+        .named_children(&mut cursor)
+        .take_while(|child| child.kind() == "doc_comment")
+        .filter_map(|child| child.utf8_text(source).ok())
+        .map(|text| {
+            let text = text.trim_start_matches("###");
+            text.strip_prefix(' ').unwrap_or(text).trim_end()
+        })
+        .collect();
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_docstring(source: &str) -> Option<String> {
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&crate::language()).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        docstring(tree.root_node().named_child(0).unwrap(), source.as_bytes())
+    }
+
+    #[test]
+    fn test_multi_line_docstring() {
+        let source = "func inc(x) {\n  ### Add one\n  ###\n  ### to x\n  return (x + 1)\n}\n";
+        assert_eq!(first_docstring(source).as_deref(), Some("Add one\n\nto x"));
+    }
+
+    #[test]
+    fn test_missing_docstring() {
+        assert_eq!(
+            first_docstring("proc p {\n  # not a doc\n  echo\n}\n"),
+            None
+        );
+        assert_eq!(first_docstring("### not a proc\necho\n"), None);
+    }
+}
@@ -0,0 +1,322 @@
+//! Static extraction of Hay ("Hay Ain't YAML") config blocks to JSON, without
+//! running a shell.
+//!
+//! ```
+//! use serde_json::json;
+//!
+//! let source = "hay define Package/TASK\n\
+//!               Package cpython {\n  version = '3.12'\n  TASK build {\n    make\n  }\n}\n";
+//! let mut parser = tree_sitter::Parser::new();
+//! parser.set_language(&tree_sitter_ysh::language()).unwrap();
+//! let tree = parser.parse(source, None).unwrap();
+//!
+//! let result = tree_sitter_ysh::hay::extract(tree.root_node(), source.as_bytes());
+//! assert_eq!(result["children"][0]["type"], "Package");
+//! assert_eq!(result["children"][0]["args"], json!(["cpython"]));
+//! assert_eq!(result["children"][0]["attrs"], json!({"version": "3.12"}));
+//! assert_eq!(result["children"][0]["children"][0]["code_str"], "\n    make\n  ");
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde_json::{json, Map, Number, Value};
+use tree_sitter::Node;
+
+/// The node types allowed at one level of a Hay tree, as declared by
+/// `hay define Package/TASK`.
+#[derive(Default)]
+struct Schema {
+    children: BTreeMap<String, Schema>,
+}
+
+/// Extract the Hay nodes of a `source_file`, in the shape of the `_hay()`
+/// result that `hay eval` produces.
+///
+/// Node types come from the file's top-level `hay define` commands, and
+/// `haynode Type ...` is always a node. Attribute nodes get `type`, `args`,
+/// `attrs` and `children`; types in all caps, like `TASK`, hold shell code
+/// and get `code_str` and `location_start_line` instead. Attributes whose
+/// value is not a literal, and arguments that are not plain words or
+/// strings, are `null`, since evaluating them would need a shell.
+pub fn extract(root: Node<'_>, source: &[u8]) -> Value {
+    let schema = definitions(root, source);
+    json!({ "children": children(root, &schema, source) })
+}
+
+fn definitions(root: Node<'_>, source: &[u8]) -> Schema {
+    let mut schema = Schema::default();
+    let mut cursor = root.walk();
+    for command in root.named_children(&mut cursor) {
+        let Some((name, args)) = command_words(command, source) else {
+            continue;
+        };
+        if name != "hay" || args.first() != Some(&Some("define".to_string())) {
+            continue;
+        }
+        for path in args[1..].iter().flatten() {
+            let mut level = &mut schema;
+            for ty in path.split('/') {
+                level = level.children.entry(ty.to_string()).or_default();
+            }
+        }
+    }
+    schema
+}
+
+// The Hay nodes among the statements of `parent`
+fn children(parent: Node<'_>, schema: &Schema, source: &[u8]) -> Vec<Value> {
+    let mut cursor = parent.walk();
+    parent
+        .named_children(&mut cursor)
+        .filter_map(|child| hay_node(child, schema, source))
+        .collect()
+}
+
+fn hay_node(command: Node<'_>, schema: &Schema, source: &[u8]) -> Option<Value> {
+    let (name, mut args) = command_words(command, source)?;
+    let empty = Schema::default();
+    let (ty, child_schema) = if name == "haynode" && !args.is_empty() {
+        (args.remove(0)?, &empty)
+    } else {
+        let child_schema = schema.children.get(&name)?;
+        (name, child_schema)
+    };
+    let args: Vec<Value> = args.into_iter().map(Value::from).collect();
+
+    let mut node = Map::new();
+    node.insert("type".into(), ty.clone().into());
+    node.insert("args".into(), args.into());
+
+    let block = command.child_by_field_name("block");
+    if is_code_type(&ty) {
+        if let Some(block) = block {
+            let code = &source[block.start_byte() + 1..block.end_byte() - 1];
+            node.insert("code_str".into(), String::from_utf8_lossy(code).into());
+            node.insert(
+                "location_start_line".into(),
+                (block.start_position().row + 1).into(),
+            );
+        }
+    } else {
+        let (attrs, children) = match block {
+            Some(block) => (attrs(block, source), children(block, child_schema, source)),
+            None => (Map::new(), Vec::new()),
+        };
+        node.insert("children".into(), children.into());
+        node.insert("attrs".into(), attrs.into());
+    }
+    Some(node.into())
+}
+
+// TASK, but not Package
+fn is_code_type(ty: &str) -> bool {
+    ty.chars().any(|c| c.is_ascii_uppercase()) && !ty.chars().any(|c| c.is_ascii_lowercase())
+}
+
+fn attrs(block: Node<'_>, source: &[u8]) -> Map<String, Value> {
+    let mut cursor = block.walk();
+    block
+        .named_children(&mut cursor)
+        .filter(|child| child.kind() == "hay_attribute")
+        .filter_map(|attr| {
+            let name = attr.child_by_field_name("name")?.utf8_text(source).ok()?;
+            let value = attr
+                .child_by_field_name("value")
+                .and_then(|value| literal(value, source))
+                .unwrap_or(Value::Null);
+            Some((name.to_string(), value))
+        })
+        .collect()
+}
+
+// The static name and arguments of a simple command, with `None` for
+// arguments that need a shell to evaluate
+fn command_words(command: Node<'_>, source: &[u8]) -> Option<(String, Vec<Option<String>>)> {
+    if command.kind() != "simple_command" {
+        return None;
+    }
+    let name = command.child_by_field_name("name")?.named_child(0)?;
+    let name = string_value(name, source)?;
+
+    let mut cursor = command.walk();
+    let args = command
+        .children_by_field_name("argument", &mut cursor)
+        .map(|word| string_value(word, source))
+        .collect();
+    Some((name, args))
+}
+
+// The value of an expression made only of literals
+fn literal(node: Node<'_>, source: &[u8]) -> Option<Value> {
+    let text = node.utf8_text(source).ok()?;
+    match node.kind() {
+        "number" => literal(node.named_child(0)?, source),
+        "integer" => {
+            let digits = text.replace('_', "");
+            let value = match digits.get(..2) {
+                Some("0x" | "0X") => i64::from_str_radix(&digits[2..], 16),
+                Some("0o" | "0O") => i64::from_str_radix(&digits[2..], 8),
+                Some("0b" | "0B") => i64::from_str_radix(&digits[2..], 2),
+                _ => digits.parse(),
+            };
+            value.ok().map(Value::from)
+        }
+        "float" => Number::from_f64(text.replace('_', "").parse().ok()?).map(Value::Number),
+        "boolean_literal" => Some(Value::Bool(text == "true")),
+        "null_literal" => Some(Value::Null),
+        "string" => string_value(node, source).map(Value::String),
+        "parenthesized_expression" => literal(node.named_child(0)?, source),
+        "unary_expression" => {
+            let operator = node
+                .child_by_field_name("operator")?
+                .utf8_text(source)
+                .ok()?;
+            let argument = literal(node.child_by_field_name("argument")?, source)?;
+            match (operator, argument) {
+                ("+", Value::Number(n)) => Some(Value::Number(n)),
+                ("-", Value::Number(n)) => match n.as_i64() {
+                    Some(i) => i.checked_neg().map(Value::from),
+                    None => Number::from_f64(-n.as_f64()?).map(Value::Number),
+                },
+                _ => None,
+            }
+        }
+        "list_literal" => {
+            let mut cursor = node.walk();
+            let items = node
+                .named_children(&mut cursor)
+                .map(|item| literal(item, source))
+                .collect::<Option<Vec<_>>>()?;
+            Some(Value::Array(items))
+        }
+        "dict_literal" => {
+            let mut cursor = node.walk();
+            let pairs = node
+                .named_children(&mut cursor)
+                .map(|pair| {
+                    let key = pair.child_by_field_name("key")?;
+                    let key = match key.kind() {
+                        "string" => string_value(key, source)?,
+                        _ => key.utf8_text(source).ok()?.to_string(),
+                    };
+                    Some((key, literal(pair.child_by_field_name("value")?, source)?))
+                })
+                .collect::<Option<Map<_, _>>>()?;
+            Some(Value::Object(pairs))
+        }
+        _ => None,
+    }
+}
+
+// The value of a string or word with no substitutions, if it's UTF-8
+fn string_value(node: Node<'_>, source: &[u8]) -> Option<String> {
+    String::from_utf8(crate::literal::decode_string(node, source).ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract_source(source: &str) -> Value {
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&crate::language()).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        extract(tree.root_node(), source.as_bytes())
+    }
+
+    #[test]
+    fn test_nested_nodes_and_attrs() {
+        let source = r#"hay define 'Package/TASK'
+hay define Rule
+
+Package cpython {
+  version = '3.12'
+  deps = ['libffi', "zlib", u'\u{1F40D}']
+  meta = {stable: true, jobs: -4, ratio: 0.5}
+  TASK build {
+    make
+  }
+  Rule nested {
+    x = 1
+  }
+}
+Rule lint $target {
+  cmd = "$CC --lint"
+}
+Package 'my pkg' "v2" {
+  x = 1
+}
+echo ignored { x = 1 }
+"#;
+        assert_eq!(
+            extract_source(source),
+            json!({
+                "children": [
+                    {
+                        "type": "Package",
+                        "args": ["cpython"],
+                        "attrs": {
+                            "version": "3.12",
+                            "deps": ["libffi", "zlib", "\u{1F40D}"],
+                            "meta": {"stable": true, "jobs": -4, "ratio": 0.5},
+                        },
+                        "children": [
+                            {
+                                "type": "TASK",
+                                "args": ["build"],
+                                "code_str": "\n    make\n  ",
+                                "location_start_line": 8,
+                            },
+                        ],
+                    },
+                    {
+                        "type": "Rule",
+                        "args": ["lint", null],
+                        "attrs": {"cmd": null},
+                        "children": [],
+                    },
+                    {
+                        "type": "Package",
+                        "args": ["my pkg", "v2"],
+                        "attrs": {"x": 1},
+                        "children": [],
+                    },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn test_haynode() {
+        let source = "haynode Config {\n  port = 0x1F90\n  haynode Inner\n}\n";
+        assert_eq!(
+            extract_source(source),
+            json!({
+                "children": [{
+                    "type": "Config",
+                    "args": [],
+                    "attrs": {"port": 8080},
+                    "children": [
+                        {"type": "Inner", "args": [], "attrs": {}, "children": []},
+                    ],
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn test_concatenated_arguments() {
+        let source = "hay define Package\nPackage lib/'my dir'\"-v2\" v$x {\n  x = 1\n}\n";
+        assert_eq!(
+            extract_source(source),
+            json!({
+                "children": [{
+                    "type": "Package",
+                    "args": ["lib/my dir-v2", null],
+                    "attrs": {"x": 1},
+                    "children": [],
+                }],
+            })
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! Syntax highlighting for YSH, driven by the grammar's own queries.
+//!
+//! ```
+//! let mut highlighter = tree_sitter_ysh::highlight::Highlighter::new();
+//! let html = highlighter.html("var x = 42\n").unwrap();
+//! assert!(html.contains(r#"<span class="keyword">var</span>"#));
+//! ```
+
+use tree_sitter::QueryError;
+use tree_sitter_highlight::{
+    Error, Highlight, HighlightConfiguration, HighlightEvent, HtmlRenderer,
+};
+
+/// The capture names [`configuration`] recognizes, in [`Highlight`] index order.
+///
+/// Captures in the queries that are not listed here fall back to their
+/// longest listed prefix, e.g. `@number.hex` highlights as `number`.
+pub const HIGHLIGHT_NAMES: &[&str] = &[
+    "attribute",
+    "character",
+    "comment",
+    "comment.documentation",
+    "constant",
+    "constant.builtin",
+    "function",
+    "function.call",
+    "function.definition",
+    "function.method",
+    "keyword",
+    "keyword.control",
+    "keyword.function",
+    "keyword.operator",
+    "number",
+    "number.float",
+    "operator",
+    "property",
+    "punctuation",
+    "punctuation.bracket",
+    "punctuation.delimiter",
+    "punctuation.special",
+    "string",
+    "string.escape",
+    "string.regex",
+    "string.special",
+    "type",
+    "variable",
+    "variable.builtin",
+    "variable.parameter",
+    "variable.special",
+];
+
+/// Build a [`HighlightConfiguration`] from the highlights, injections and
+/// locals queries, configured with [`HIGHLIGHT_NAMES`].
+pub fn configuration() -> Result<HighlightConfiguration, QueryError> {
+    let mut config = HighlightConfiguration::new(
+        crate::language(),
+        "ysh",
+        crate::HIGHLIGHTS_QUERY,
+        crate::INJECTIONS_QUERY,
+        crate::LOCALS_QUERY,
+    )?;
+    config.configure(HIGHLIGHT_NAMES);
+    Ok(config)
+}
+
+/// Renders YSH source as HTML or as ANSI-colored terminal text.
+///
+/// Keep one around to reuse its parser across files.
+pub struct Highlighter {
+    config: HighlightConfiguration,
+    highlighter: tree_sitter_highlight::Highlighter,
+}
+
+impl Highlighter {
+    /// Create a highlighter with the [`configuration`] for YSH.
+    pub fn new() -> Self {
+        Self {
+            config: configuration().expect("YSH highlight queries are invalid"),
+            highlighter: tree_sitter_highlight::Highlighter::new(),
+        }
+    }
+
+    /// Render `source` as HTML, wrapping each highlight in a
+    /// `<span class="...">` whose class is the capture name with `.`
+    /// replaced by a space, e.g. `class="keyword control"`.
+    pub fn html(&mut self, source: &str) -> Result<String, Error> {
+        let classes: Vec<String> = HIGHLIGHT_NAMES
+            .iter()
+            .map(|name| format!(r#"class="{}""#, name.replace('.', " ")))
+            .collect();
+
+        let config = &self.config;
+        let events = self
+            .highlighter
+            .highlight(config, source.as_bytes(), None, |_| None)?;
+        let mut renderer = HtmlRenderer::new();
+        renderer.render(events, source.as_bytes(), &|Highlight(i)| {
+            classes[i].as_bytes()
+        })?;
+        Ok(String::from_utf8(renderer.html).unwrap())
+    }
+
+    /// Render `source` with ANSI escape codes for a terminal.
+    pub fn ansi(&mut self, source: &str) -> Result<String, Error> {
+        let config = &self.config;
+        let events = self
+            .highlighter
+            .highlight(config, source.as_bytes(), None, |_| None)?;
+
+        let mut out = String::with_capacity(source.len());
+        let mut stack: Vec<Highlight> = Vec::new();
+        for event in events {
+            match event? {
+                HighlightEvent::Source { start, end } => out.push_str(&source[start..end]),
+                HighlightEvent::HighlightStart(highlight) => {
+                    stack.push(highlight);
+                    out.push_str(ansi_style(highlight));
+                }
+                HighlightEvent::HighlightEnd => {
+                    stack.pop();
+                    out.push_str("\x1b[0m");
+                    if let Some(&outer) = stack.last() {
+                        out.push_str(ansi_style(outer));
+                    }
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+impl Default for Highlighter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The SGR escape for a highlight, chosen by its capture name.
+fn ansi_style(Highlight(i): Highlight) -> &'static str {
+    let name = HIGHLIGHT_NAMES[i];
+    match name {
+        "comment.documentation" => "\x1b[3;32m",
+        "keyword.operator" | "operator" => "\x1b[36m",
+        "character" | "string.escape" | "string.regex" | "string.special" => "\x1b[33m",
+        "variable.builtin" | "constant.builtin" => "\x1b[1;35m",
+        "variable.parameter" => "\x1b[3m",
+        _ => match name.split('.').next().unwrap() {
+            "comment" => "\x1b[90m",
+            "keyword" => "\x1b[35m",
+            "string" => "\x1b[32m",
+            "number" | "constant" => "\x1b[33m",
+            "function" => "\x1b[34m",
+            "type" | "attribute" | "property" => "\x1b[36m",
+            "punctuation" => "\x1b[37m",
+            _ => "\x1b[39m",
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configuration_recognizes_query_captures() {
+        let config = configuration().unwrap();
+        for name in config.names() {
+            if name.starts_with("local.") {
+                continue;
+            }
+            assert!(
+                HIGHLIGHT_NAMES.contains(name),
+                "@{name} is missing from HIGHLIGHT_NAMES"
+            );
+        }
+    }
+
+    #[test]
+    fn test_html() {
+        let mut highlighter = Highlighter::new();
+        let html = highlighter
+            .html("proc greet (name) {\n  echo \"hi <$name>\"\n}\n")
+            .unwrap();
+        assert!(html.contains(r#"<span class="keyword">proc</span>"#));
+        assert!(html.contains(r#"<span class="function definition">greet</span>"#));
+        assert!(html.contains(r#"<span class="function call">echo</span>"#));
+        assert!(html.contains("&lt;"), "source text is escaped: {html}");
+    }
+
+    #[test]
+    fn test_ansi() {
+        let mut highlighter = Highlighter::new();
+        let source = "if (x === 0) {\n  echo zero  # done\n}\n";
+        let ansi = highlighter.ansi(source).unwrap();
+        assert!(ansi.contains("\x1b[35mif\x1b[0m"));
+        assert!(ansi.contains("\x1b[90m# done\x1b[0m"));
+
+        let plain = ansi
+            .split('\x1b')
+            .enumerate()
+            .map(|(i, part)| {
+                if i == 0 {
+                    part
+                } else {
+                    &part[part.find('m').unwrap() + 1..]
+                }
+            })
+            .collect::<String>();
+        assert_eq!(plain, source);
+    }
+}
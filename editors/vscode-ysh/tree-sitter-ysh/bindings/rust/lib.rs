@@ -2,8 +2,20 @@
 
 use tree_sitter::Language;
 
+pub mod ast;
+pub mod dialect;
+pub mod docs;
+#[cfg(feature = "hay")]
+pub mod hay;
+#[cfg(feature = "highlight")]
+pub mod highlight;
+pub mod literal;
+
+pub use dialect::{detect_dialect, Dialect};
+
 extern "C" {
     fn tree_sitter_ysh() -> Language;
+    fn tree_sitter_osh() -> Language;
 }
 
 /// Get the tree-sitter [Language][] for YSH.
@@ -13,6 +25,16 @@ pub fn language() -> Language {
     unsafe { tree_sitter_ysh() }
 }
 
+/// Get the tree-sitter [Language][] for OSH, the bash-compatible dialect.
+///
+/// Its grammar extends the YSH one, so the two share node kinds, but OSH has
+/// no expression mode: `var x` is a simple command named `var`.
+///
+/// [Language]: https://docs.rs/tree-sitter/*/tree_sitter/struct.Language.html
+pub fn osh_language() -> Language {
+    unsafe { tree_sitter_osh() }
+}
+
 /// The content of the [`node-types.json`][] file for this grammar.
 ///
 /// [`node-types.json`]: https://tree-sitter.github.io/tree-sitter/using-parsers#static-node-types
@@ -24,14 +46,233 @@ pub const HIGHLIGHTS_QUERY: &str = include_str!("../../queries/highlights.scm");
 /// The local variable queries for this grammar.
 pub const LOCALS_QUERY: &str = include_str!("../../queries/locals.scm");
 
+/// The symbol tagging queries for this grammar, for code navigation.
+pub const TAGS_QUERY: &str = include_str!("../../queries/tags.scm");
+
+/// The language injection queries for this grammar.
+pub const INJECTIONS_QUERY: &str = include_str!("../../queries/injections.scm");
+
+/// The content of the [`node-types.json`][] file for the OSH grammar.
+///
+/// [`node-types.json`]: https://tree-sitter.github.io/tree-sitter/using-parsers#static-node-types
+pub const OSH_NODE_TYPES: &str = include_str!("../../osh/src/node-types.json");
+
+/// The syntax highlighting queries for the OSH grammar.
+pub const OSH_HIGHLIGHTS_QUERY: &str = include_str!("../../osh/queries/highlights.scm");
+
+/// Build a [`TagsConfiguration`][] for YSH from [`TAGS_QUERY`] and [`LOCALS_QUERY`].
+///
+/// [`TagsConfiguration`]: https://docs.rs/tree-sitter-tags/*/tree_sitter_tags/struct.TagsConfiguration.html
+#[cfg(feature = "tags")]
+pub fn tags_configuration() -> Result<tree_sitter_tags::TagsConfiguration, tree_sitter_tags::Error>
+{
+    tree_sitter_tags::TagsConfiguration::new(language(), TAGS_QUERY, LOCALS_QUERY)
+}
+
+// Field ids, e.g. `FIELD_NAME`, taken from the generated parser.
+include!(concat!(env!("OUT_DIR"), "/fields.rs"));
+
 #[cfg(test)]
 mod tests {
+    use std::fs;
+    use std::io::ErrorKind;
+    use std::path::Path;
+    use std::process::Command;
+
     #[test]
     fn test_can_load_grammar() {
         let mut parser = tree_sitter::Parser::new();
         parser
-            .set_language(super::language())
+            .set_language(&super::language())
             .expect("Error loading YSH grammar");
     }
-}
 
+    #[test]
+    fn test_osh_parses_ysh_keywords_as_commands() {
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&super::osh_language())
+            .expect("Error loading OSH grammar");
+        let tree = parser.parse("var x\n", None).unwrap();
+        let command = tree.root_node().named_child(0).unwrap();
+        assert_eq!(command.kind(), "simple_command");
+        assert!(!tree.root_node().has_error());
+
+        tree_sitter::Query::new(&super::osh_language(), super::OSH_HIGHLIGHTS_QUERY)
+            .expect("Error compiling OSH highlights query");
+    }
+
+    #[test]
+    fn test_field_ids_match_language() {
+        let language = super::language();
+        for (id, name) in [
+            (super::FIELD_NAME, "name"),
+            (super::FIELD_BODY, "body"),
+            (super::FIELD_CONDITION, "condition"),
+            (super::FIELD_VALUE, "value"),
+        ] {
+            assert_eq!(language.field_name_for_id(id), Some(name));
+        }
+
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&language).unwrap();
+        let tree = parser.parse("proc greet (name) { echo hi }", None).unwrap();
+        let proc_def = tree.root_node().named_child(0).unwrap();
+        let name = proc_def.child_by_field_id(super::FIELD_NAME).unwrap();
+        assert_eq!(name.kind(), "identifier");
+        assert_eq!(name.start_byte(), 5);
+    }
+
+    #[test]
+    fn test_function_definition_at_end_of_file() {
+        // Corpus inputs always end in a newline, which hid this case
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&super::language()).unwrap();
+        for source in ["f() { echo; }", "myfunc() {\n  echo hello\n}"] {
+            let tree = parser.parse(source, None).unwrap();
+            let node = tree.root_node().named_child(0).unwrap();
+            assert_eq!(node.kind(), "function_definition", "{source:?}");
+            assert!(!tree.root_node().has_error());
+        }
+    }
+
+    /// Parses a heredoc with `delimiter` and checks that the error stays on
+    /// its line rather than the body swallowing the rest of the file.
+    fn assert_heredoc_rejected(delimiter: &str) {
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&super::language()).unwrap();
+        let source = format!("cat <<{delimiter}\nbody\n{delimiter}\necho after\n");
+        let tree = parser.parse(&source, None).unwrap();
+        let root = tree.root_node();
+        assert!(root.has_error());
+
+        let last = root.named_child(root.named_child_count() - 1).unwrap();
+        assert_eq!(last.kind(), "simple_command");
+        assert_eq!(last.utf8_text(source.as_bytes()).unwrap(), "echo after");
+    }
+
+    #[test]
+    fn test_heredoc_delimiter_too_long() {
+        assert_heredoc_rejected(&"D".repeat(65));
+    }
+
+    #[test]
+    fn test_heredoc_delimiter_not_ascii() {
+        assert_heredoc_rejected("FINé");
+    }
+
+    #[test]
+    fn test_typed_ast() {
+        use super::ast::{AnyNode, AstNode, ProcDefinition, SourceFile};
+
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&super::language()).unwrap();
+        let tree = parser
+            .parse("proc greet (name) {\n  echo hi\n}\n", None)
+            .unwrap();
+
+        let file = SourceFile::cast(tree.root_node()).unwrap();
+        let children = file.children();
+        assert_eq!(children.len(), 1);
+        let AnyNode::ProcDefinition(proc_def) = children[0] else {
+            panic!("expected a proc definition, got {:?}", children[0]);
+        };
+        assert!(ProcDefinition::cast(proc_def.node()).is_some());
+        assert!(SourceFile::cast(proc_def.node()).is_none());
+
+        let name = proc_def.name().unwrap();
+        assert_eq!(name.node().utf8_text(b"proc greet").unwrap(), "greet");
+        assert!(proc_def.body().is_some());
+    }
+
+    #[cfg(feature = "tags")]
+    #[test]
+    fn test_tags_configuration() {
+        let config = super::tags_configuration().unwrap();
+        let source =
+            b"proc greet (name) {\n  echo hi\n}\nconst PI = 3.14159\nvar area = PI * square(r)\nvar w, h = 1, 2\n";
+        let mut context = tree_sitter_tags::TagsContext::new();
+        let (tags, _) = context.generate_tags(&config, source, None).unwrap();
+
+        let tags: Vec<_> = tags
+            .map(|tag| {
+                let tag = tag.unwrap();
+                let name = std::str::from_utf8(&source[tag.name_range.clone()]).unwrap();
+                (
+                    config.syntax_type_name(tag.syntax_type_id),
+                    tag.is_definition,
+                    name,
+                )
+            })
+            .collect();
+        assert!(tags.contains(&("function", true, "greet")));
+        assert!(tags.contains(&("constant", true, "PI")));
+        assert!(tags.contains(&("variable", true, "area")));
+        assert!(tags.contains(&("variable", true, "w")));
+        assert!(tags.contains(&("variable", true, "h")));
+        assert!(tags.contains(&("call", false, "echo")));
+        assert!(tags.contains(&("call", false, "square")));
+    }
+
+    /// The generated parsers are committed so the crate builds without the
+    /// tree-sitter CLI. Regenerate them from `grammar.js` and
+    /// `osh/grammar.js` in a scratch directory and make sure the committed
+    /// files match. This needs the CLI version from `package.json`, so run it
+    /// with `cargo test -- --ignored`.
+    #[test]
+    #[ignore = "needs the tree-sitter CLI"]
+    fn test_generated_files_are_up_to_date() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+        // Other CLI versions generate different, equally valid parsers
+        let package = fs::read_to_string(root.join("package.json")).unwrap();
+        let pinned = package
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("\"tree-sitter-cli\": \"^"))
+            .expect("package.json has no tree-sitter-cli devDependency");
+        let (major, rest) = pinned.split_once('.').unwrap();
+        let minor = rest.split('.').next().unwrap();
+        let output = match Command::new("tree-sitter").arg("--version").output() {
+            Ok(output) => output,
+            Err(e) if e.kind() == ErrorKind::NotFound => panic!("tree-sitter CLI not found"),
+            Err(e) => panic!("Error running tree-sitter --version: {e}"),
+        };
+        let version = String::from_utf8(output.stdout).unwrap();
+        let prefix = format!("tree-sitter {major}.{minor}.");
+        assert!(
+            version.starts_with(&prefix),
+            "expected tree-sitter CLI {major}.{minor}, found {}",
+            version.trim()
+        );
+
+        let scratch =
+            std::env::temp_dir().join(format!("tree-sitter-ysh-generate-{}", std::process::id()));
+        fs::create_dir_all(scratch.join("osh")).unwrap();
+        fs::create_dir_all(scratch.join("common")).unwrap();
+        for name in ["grammar.js", "osh/grammar.js", "common/rules.js"] {
+            fs::copy(root.join(name), scratch.join(name)).unwrap();
+        }
+
+        for dir in ["", "osh"] {
+            let status = Command::new("tree-sitter")
+                .args(["generate", "--no-bindings"])
+                .current_dir(scratch.join(dir))
+                .status()
+                .expect("Error running tree-sitter generate");
+            assert!(status.success(), "tree-sitter generate failed in {dir:?}");
+
+            let src = Path::new(dir).join("src");
+            for name in ["grammar.json", "node-types.json", "parser.c"] {
+                let committed = fs::read_to_string(root.join(&src).join(name)).unwrap();
+                let generated = fs::read_to_string(scratch.join(&src).join(name)).unwrap();
+                assert!(
+                    committed == generated,
+                    "{} is out of date; run `tree-sitter generate` and commit the result",
+                    src.join(name).display()
+                );
+            }
+        }
+
+        fs::remove_dir_all(&scratch).unwrap();
+    }
+}
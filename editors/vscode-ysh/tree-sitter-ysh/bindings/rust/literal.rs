@@ -0,0 +1,313 @@
+//! The values of string literals, with quotes removed and escapes decoded.
+//!
+//! ```
+//! let source = "var s = u'caf\\u{e9}\\n'\n";
+//! let mut parser = tree_sitter::Parser::new();
+//! parser.set_language(&tree_sitter_ysh::language()).unwrap();
+//! let tree = parser.parse(source, None).unwrap();
+//!
+//! let value = tree.root_node().named_child(0).unwrap().child_by_field_name("value").unwrap();
+//! let bytes = tree_sitter_ysh::literal::decode_string(value, source.as_bytes()).unwrap();
+//! assert_eq!(bytes, "café\n".as_bytes());
+//! ```
+
+use std::fmt;
+use std::ops::Range;
+
+use tree_sitter::Node;
+
+/// Why a node has no constant string value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The node is not a string literal or a plain word.
+    NotAString(String),
+    /// The string contains a syntax error.
+    Syntax(Range<usize>),
+    /// The string contains a substitution, like `$name`, so its value is only
+    /// known when it runs.
+    Substitution(Range<usize>),
+    /// An escape that is invalid where it appears, like a surrogate in
+    /// `\u{d800}`.
+    InvalidEscape(Range<usize>),
+    /// A `u'...'` string whose value is not valid UTF-8.
+    InvalidUtf8(Range<usize>),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::NotAString(kind) => write!(f, "{kind} is not a string literal"),
+            DecodeError::Syntax(range) => write!(f, "syntax error in string at {range:?}"),
+            DecodeError::Substitution(range) => {
+                write!(f, "substitution at {range:?} has no constant value")
+            }
+            DecodeError::InvalidEscape(range) => write!(f, "invalid escape at {range:?}"),
+            DecodeError::InvalidUtf8(range) => write!(f, "u'' string at {range:?} is not UTF-8"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// How backslashes are interpreted inside a kind of string.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Escapes {
+    /// '...', r'...' and '''...'''
+    None,
+    /// "...", where only \\, \", \$ and \` are escapes
+    Double,
+    /// $'...'
+    C,
+    /// u'...' and b'...'
+    J8,
+}
+
+/// Decode the value of a `string` node, one of its kinds like `j8_string`, or
+/// a `word` made of bare words and strings, like `lib/'my dir'`.
+///
+/// Multi-line strings are dedented like YSH does: a newline right after the
+/// opening quotes is dropped, and the whitespace before the closing quotes is
+/// removed from the start of every line.
+pub fn decode_string(node: Node<'_>, source: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    // word -> string -> single_quoted_string
+    let mut node = node;
+    while matches!(node.kind(), "word" | "string") && node.named_child_count() == 1 {
+        node = node.named_child(0).unwrap();
+    }
+    if node.has_error() {
+        return Err(DecodeError::Syntax(node.byte_range()));
+    }
+    if node.kind() == "concatenation" {
+        let mut value = Vec::new();
+        let mut cursor = node.walk();
+        for part in node.named_children(&mut cursor) {
+            value.extend(decode_string(part, source)?);
+        }
+        return Ok(value);
+    }
+
+    let text = &source[node.byte_range()];
+    let (escapes, quote_len) = match node.kind() {
+        "bare_word" => return Ok(text.to_vec()),
+        "single_quoted_string" | "raw_string" => (Escapes::None, 1),
+        "double_quoted_string" => (Escapes::Double, 1),
+        "dollar_single_quoted_string" => (Escapes::C, 1),
+        "j8_string" => (Escapes::J8, 1),
+        "multiline_single_string" => (Escapes::None, 3),
+        "multiline_double_string" => (Escapes::Double, 3),
+        "multiline_j8_string" => (Escapes::J8, 3),
+        kind => return Err(DecodeError::NotAString(kind.to_string())),
+    };
+    // The r, $, u or b before the quotes
+    let prefix_len = usize::from(!matches!(text[0], b'\'' | b'"'));
+    let start = node.start_byte() + prefix_len + quote_len;
+    let end = node.end_byte() - quote_len;
+
+    let skipped = if quote_len == 3 {
+        dedent(source, start, end)
+    } else {
+        Vec::new()
+    };
+
+    let mut value = Vec::new();
+    let mut position = start;
+    let mut cursor = node.walk();
+    for part in node.named_children(&mut cursor) {
+        if part.kind() != "escape_sequence" {
+            return Err(DecodeError::Substitution(part.byte_range()));
+        }
+        push_text(&mut value, source, position..part.start_byte(), &skipped);
+        decode_escape(&mut value, &source[part.byte_range()], escapes)
+            .ok_or_else(|| DecodeError::InvalidEscape(part.byte_range()))?;
+        position = part.end_byte();
+    }
+    push_text(&mut value, source, position..end, &skipped);
+
+    if escapes == Escapes::J8 && text[0] == b'u' && std::str::from_utf8(&value).is_err() {
+        return Err(DecodeError::InvalidUtf8(node.byte_range()));
+    }
+    Ok(value)
+}
+
+// The byte ranges that multi-line string dedenting removes from
+// `source[start..end]`
+fn dedent(source: &[u8], start: usize, end: usize) -> Vec<Range<usize>> {
+    let body = &source[start..end];
+    let indent = body
+        .iter()
+        .rev()
+        .take_while(|&&b| b == b' ' || b == b'\t')
+        .count();
+    let closes_own_line = body.len() > indent && body[body.len() - indent - 1] == b'\n';
+    let indent = if closes_own_line { indent } else { 0 };
+
+    let mut skipped = Vec::new();
+    let mut line_start = start;
+    if body.first() == Some(&b'\n') {
+        skipped.push(start..start + 1);
+        line_start += 1;
+    } else if body.starts_with(b"\r\n") {
+        skipped.push(start..start + 2);
+        line_start += 2;
+    }
+    while line_start < end {
+        let line = &source[line_start..end];
+        let strip = line
+            .iter()
+            .take(indent)
+            .take_while(|&&b| b == b' ' || b == b'\t')
+            .count();
+        skipped.push(line_start..line_start + strip);
+        match line.iter().position(|&b| b == b'\n') {
+            Some(newline) => line_start += newline + 1,
+            None => break,
+        }
+    }
+    skipped
+}
+
+fn push_text(value: &mut Vec<u8>, source: &[u8], range: Range<usize>, skipped: &[Range<usize>]) {
+    value.extend(
+        range
+            .filter(|i| !skipped.iter().any(|s| s.contains(i)))
+            .map(|i| source[i]),
+    );
+}
+
+// Append the value of one escape, or return `None` if it's invalid
+fn decode_escape(value: &mut Vec<u8>, escape: &[u8], escapes: Escapes) -> Option<()> {
+    let escape = std::str::from_utf8(escape).ok()?;
+    let rest = &escape[1..];
+    let first = rest.chars().next()?;
+
+    let simple = match first {
+        '\\' | '\'' | '"' => Some(first as u8),
+        'n' => Some(b'\n'),
+        'r' => Some(b'\r'),
+        't' => Some(b'\t'),
+        'b' => Some(0x08),
+        'f' => Some(0x0c),
+        _ => None,
+    };
+
+    match escapes {
+        Escapes::None => value.extend_from_slice(escape.as_bytes()),
+        Escapes::Double => match first {
+            '\\' | '"' | '$' | '`' => value.push(first as u8),
+            _ => value.extend_from_slice(escape.as_bytes()),
+        },
+        Escapes::J8 => match first {
+            '/' => value.push(b'/'),
+            'y' => value.push(u8::from_str_radix(&rest[1..], 16).ok()?),
+            'u' => push_char(
+                value,
+                u32::from_str_radix(rest[2..].strip_suffix('}')?, 16).ok()?,
+            )?,
+            _ => value.push(simple?),
+        },
+        Escapes::C => match first {
+            'a' => value.push(0x07),
+            'e' => value.push(0x1b),
+            'v' => value.push(0x0b),
+            '?' => value.push(b'?'),
+            'x' => value.push(u8::from_str_radix(&rest[1..], 16).ok()?),
+            'u' | 'U' => push_char(value, u32::from_str_radix(&rest[1..], 16).ok()?)?,
+            // Like bash, \cX is X with only its low five bits
+            'c' => value.push(rest.as_bytes().get(1)? & 0x1f),
+            '0'..='7' => value.push(u32::from_str_radix(rest, 8).ok()? as u8),
+            _ => value.push(simple?),
+        },
+    }
+    Some(())
+}
+
+fn push_char(value: &mut Vec<u8>, code_point: u32) -> Option<()> {
+    let c = char::from_u32(code_point)?;
+    value.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The value of `var x = <literal>`
+    fn decode(literal: &str) -> Result<Vec<u8>, DecodeError> {
+        let source = format!("var x = {literal}\n");
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&crate::language()).unwrap();
+        let tree = parser.parse(&source, None).unwrap();
+        let decl = tree.root_node().named_child(0).unwrap();
+        decode_string(
+            decl.child_by_field_name("value").unwrap(),
+            source.as_bytes(),
+        )
+    }
+
+    #[test]
+    fn test_quoted_strings() {
+        assert_eq!(decode(r"'C:\dir\n'").unwrap(), br"C:\dir\n");
+        assert_eq!(decode(r"r'\d+'").unwrap(), br"\d+");
+        assert_eq!(decode(r#""say \"hi\"\n""#).unwrap(), br#"say "hi"\n"#);
+        assert_eq!(decode(r#""price \$5 \`x\`""#).unwrap(), b"price $5 `x`");
+        assert_eq!(
+            decode(r"$'\x41\101\u00e9\cA\e \z'").unwrap(),
+            b"AA\xc3\xa9\x01\x1b \\z"
+        );
+    }
+
+    #[test]
+    fn test_j8_strings() {
+        assert_eq!(
+            decode(r"u'caf\u{e9} \'q\' \/'").unwrap(),
+            "café 'q' /".as_bytes()
+        );
+        assert_eq!(decode(r"b'\yff\y00\t'").unwrap(), b"\xff\x00\t");
+        assert_eq!(decode("u''").unwrap(), b"");
+        assert!(matches!(
+            decode(r"u'\u{d800}'"),
+            Err(DecodeError::InvalidEscape(_))
+        ));
+        assert!(matches!(decode(r"u'\yff'"), Err(DecodeError::Syntax(_))));
+    }
+
+    #[test]
+    fn test_multiline_strings() {
+        assert_eq!(
+            decode("'''\n  one\n    two\n  '''").unwrap(),
+            b"one\n  two\n"
+        );
+        assert_eq!(decode("b'''\n  \\yff'\n  '''").unwrap(), b"\xff'\n");
+        assert_eq!(decode("r'''a\\n'''").unwrap(), b"a\\n");
+        assert_eq!(decode("\"\"\"\n  x\n  \"\"\"").unwrap(), b"x\n");
+    }
+
+    #[test]
+    fn test_command_words() {
+        let source = "echo 'a' u'\\u{e9}' lib/util.ysh $x lib/'my dir'\"/x\"\n";
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&crate::language()).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let command = tree.root_node().named_child(0).unwrap();
+
+        let mut cursor = command.walk();
+        let words: Vec<_> = command
+            .children_by_field_name("argument", &mut cursor)
+            .map(|word| decode_string(word, source.as_bytes()))
+            .collect();
+        assert_eq!(words[0], Ok(b"a".to_vec()));
+        assert_eq!(words[1], Ok("é".as_bytes().to_vec()));
+        assert_eq!(words[2], Ok(b"lib/util.ysh".to_vec()));
+        assert!(matches!(words[3], Err(DecodeError::NotAString(_))));
+        assert_eq!(words[4], Ok(b"lib/my dir/x".to_vec()));
+    }
+
+    #[test]
+    fn test_non_constant_strings() {
+        assert!(matches!(
+            decode("\"hi $name\""),
+            Err(DecodeError::Substitution(_))
+        ));
+        assert!(matches!(decode("42"), Err(DecodeError::NotAString(_))));
+    }
+}
@@ -0,0 +1,32 @@
+/// <reference types="tree-sitter-cli/dsl" />
+// @ts-check
+
+// Rule helpers shared by the YSH grammar and the OSH grammar that extends it.
+
+// Assignments with an optional command, or a command name followed by its
+// `argument`s and then the `trailing` rules: p a (x; n=1) [y] { ... }
+function simpleCommand($, argument, trailing = []) {
+  return prec.right(choice(
+    seq(
+      repeat1($.variable_assignment),
+      optional(seq(field('name', $.command_name), repeat($._command_argument))),
+    ),
+    seq(field('name', $.command_name), repeat(argument), ...trailing),
+  ));
+}
+
+// A statement list where every statement but the last needs a terminator,
+// or ends in `&`. With `name`, each statement is put in that field;
+// `statement` replaces the default `_statement` rule.
+function statements($, name, statement = $._statement) {
+  const named = rule => name ? field(name, rule) : rule;
+  return seq(
+    repeat(choice(
+      seq(named(statement), $._terminator),
+      named($.background_command),
+    )),
+    optional(named(statement)),
+  );
+}
+
+module.exports = {simpleCommand, statements};
@@ -0,0 +1,636 @@
+#ifndef TREE_SITTER_YSH_SCANNER_H_
+#define TREE_SITTER_YSH_SCANNER_H_
+
+/**
+ * External scanner shared by the YSH and OSH grammars, which have the same
+ * externals. Each dialect's src/scanner.c wraps these functions in its own
+ * tree_sitter_<name>_external_scanner_* exports.
+ *
+ * This handles context-sensitive tokens that can't be expressed in the
+ * regular grammar, such as:
+ * - Here-documents, including several pending on one line
+ * - Multiline strings with specific delimiters
+ * - Command substitution nesting
+ * - File descriptors, which are only told apart from words by the < or >
+ *   after them
+ * - Word parts with no space between them, like foo"$x"bar
+ * - Assignment names, which are only told apart from words by the = after
+ *   them
+ */
+
+#include <tree_sitter/parser.h>
+#include <stdlib.h>
+#include <wctype.h>
+#include <string.h>
+#include <stdio.h>
+
+enum TokenType {
+  HEREDOC_START,
+  STRIPPED_HEREDOC_START,
+  HEREDOC_BODY_START,
+  HEREDOC_CONTENT,
+  HEREDOC_END,
+  STRING_CONTENT,
+  MULTILINE_STRING_CONTENT,
+  REGEX_CONTENT,
+  COMMAND_SUBSTITUTION_START,
+  BRACE_EXPANSION,
+  FILE_DESCRIPTOR,
+  CONCAT,
+  VARIABLE_NAME,
+  NEWLINE,
+  ERROR_SENTINEL,
+};
+
+// Maximum heredoc delimiter length
+#define MAX_DELIMITER_LENGTH 64
+
+// Maximum number of heredocs pending on one line, e.g. cat <<A <<B
+#define MAX_PENDING_HEREDOCS 8
+
+typedef struct {
+  bool strip_tabs;
+  bool quoted;
+  uint8_t delimiter_length;
+  char delimiter[MAX_DELIMITER_LENGTH];
+} Heredoc;
+
+// Scanner state
+typedef struct {
+  // Heredocs whose bodies start after the current line, in order
+  Heredoc heredocs[MAX_PENDING_HEREDOCS];
+  uint8_t heredoc_count;
+  // Whether we are inside the body of heredocs[0]
+  bool in_heredoc_body;
+
+  // Nesting tracking
+  uint8_t paren_depth;
+  uint8_t brace_depth;
+  uint8_t bracket_depth;
+
+  // String state
+  bool in_double_quote;
+  bool in_single_quote;
+  bool in_command_sub;
+} Scanner;
+
+// Forward declarations
+static void advance(TSLexer *lexer);
+static void skip(TSLexer *lexer);
+static bool is_space(int32_t c);
+static bool is_newline(int32_t c);
+
+static void advance(TSLexer *lexer) {
+  lexer->advance(lexer, false);
+}
+
+static void skip(TSLexer *lexer) {
+  lexer->advance(lexer, true);
+}
+
+static bool is_space(int32_t c) {
+  return c == ' ' || c == '\t';
+}
+
+static bool is_newline(int32_t c) {
+  return c == '\n' || c == '\r';
+}
+
+static bool is_delimiter_char(int32_t c) {
+  return c != 0 && !is_space(c) && !is_newline(c) &&
+         c != ';' && c != '&' && c != '|' && c != '<' && c != '>' &&
+         c != '(' && c != ')' && c != '\'' && c != '"';
+}
+
+// Append a character to a heredoc delimiter. A delimiter that is too long
+// or not ASCII can't be matched against its terminator line byte by byte,
+// so it is rejected rather than cut short.
+static bool push_delimiter_char(Heredoc *heredoc, int32_t c) {
+  if (heredoc->delimiter_length == MAX_DELIMITER_LENGTH || c > 0x7F) {
+    return false;
+  }
+  heredoc->delimiter[heredoc->delimiter_length++] = (char)c;
+  return true;
+}
+
+// Scan the delimiter after << or <<-, and queue its body
+static bool scan_heredoc_start(Scanner *scanner, TSLexer *lexer, bool strip_tabs) {
+  while (is_space(lexer->lookahead)) {
+    skip(lexer);
+  }
+
+  if (scanner->heredoc_count == MAX_PENDING_HEREDOCS) {
+    return false;
+  }
+  Heredoc *heredoc = &scanner->heredocs[scanner->heredoc_count];
+  heredoc->strip_tabs = strip_tabs;
+  heredoc->quoted = false;
+  heredoc->delimiter_length = 0;
+
+  // Quoted delimiters like <<'EOF', <<"EOF" and <<\EOF make the body literal
+  while (true) {
+    int32_t c = lexer->lookahead;
+    if (c == '\'' || c == '"') {
+      heredoc->quoted = true;
+      advance(lexer);
+      while (lexer->lookahead != c && lexer->lookahead != 0 && !is_newline(lexer->lookahead)) {
+        if (!push_delimiter_char(heredoc, lexer->lookahead)) {
+          return false;
+        }
+        advance(lexer);
+      }
+      if (lexer->lookahead != c) {
+        return false;
+      }
+      advance(lexer);
+    } else if (c == '\\') {
+      heredoc->quoted = true;
+      advance(lexer);
+    } else if (is_delimiter_char(c)) {
+      if (!push_delimiter_char(heredoc, c)) {
+        return false;
+      }
+      advance(lexer);
+    } else {
+      break;
+    }
+  }
+
+  if (heredoc->delimiter_length == 0) {
+    return false;
+  }
+
+  scanner->heredoc_count++;
+  lexer->result_symbol = strip_tabs ? STRIPPED_HEREDOC_START : HEREDOC_START;
+  return true;
+}
+
+// At the end of a line with pending heredocs, consume the newline that
+// starts the first body.
+static bool scan_heredoc_body_start(Scanner *scanner, TSLexer *lexer) {
+  while (is_space(lexer->lookahead)) {
+    skip(lexer);
+  }
+
+  if (lexer->lookahead == '\r') {
+    advance(lexer);
+  }
+  if (lexer->lookahead != '\n') {
+    return false;
+  }
+  advance(lexer);
+
+  scanner->in_heredoc_body = true;
+  lexer->result_symbol = HEREDOC_BODY_START;
+  return true;
+}
+
+static void pop_heredoc(Scanner *scanner) {
+  scanner->heredoc_count--;
+  memmove(&scanner->heredocs[0], &scanner->heredocs[1],
+          scanner->heredoc_count * sizeof(Heredoc));
+  scanner->in_heredoc_body = false;
+}
+
+// Scan heredoc text up to a substitution (for unquoted delimiters) or up
+// to the closing delimiter line, which becomes HEREDOC_END.
+static bool scan_heredoc_content(Scanner *scanner, TSLexer *lexer) {
+  Heredoc *heredoc = &scanner->heredocs[0];
+  bool has_content = false;
+  bool at_line_start = lexer->get_column(lexer) == 0;
+
+  while (true) {
+    lexer->mark_end(lexer);
+
+    if (lexer->eof(lexer)) {
+      if (!has_content) {
+        // An unterminated heredoc ends with the file
+        pop_heredoc(scanner);
+        lexer->result_symbol = HEREDOC_END;
+        return true;
+      }
+      break;
+    }
+
+    if (at_line_start) {
+      at_line_start = false;
+
+      if (heredoc->strip_tabs) {
+        while (lexer->lookahead == '\t') {
+          advance(lexer);
+        }
+      }
+
+      uint8_t i = 0;
+      while (i < heredoc->delimiter_length && lexer->lookahead == heredoc->delimiter[i]) {
+        advance(lexer);
+        i++;
+      }
+      if (i == heredoc->delimiter_length &&
+          (is_newline(lexer->lookahead) || lexer->eof(lexer))) {
+        if (has_content) {
+          // End the content before the delimiter line
+          break;
+        }
+        lexer->mark_end(lexer);
+        pop_heredoc(scanner);
+        lexer->result_symbol = HEREDOC_END;
+        return true;
+      }
+      has_content = true;
+      continue;
+    }
+
+    int32_t c = lexer->lookahead;
+    if (c == '\n') {
+      at_line_start = true;
+    } else if (!heredoc->quoted && c == '\\') {
+      advance(lexer);
+      if (lexer->eof(lexer)) {
+        has_content = true;
+        continue;
+      }
+    } else if (!heredoc->quoted && c == '`') {
+      break;
+    } else if (!heredoc->quoted && c == '$') {
+      advance(lexer);
+      int32_t next = lexer->lookahead;
+      if (iswalpha(next) || iswdigit(next) || next == '_' || next == '{' ||
+          next == '(' || next == '[' ||
+          // strchr() matches the terminating NUL, and only looks at a char
+          (next > 0 && next < 0x80 && strchr("!@#$*?-", next) != NULL)) {
+        break;
+      }
+      has_content = true;
+      continue;
+    }
+    advance(lexer);
+    has_content = true;
+  }
+
+  if (!has_content) {
+    return false;
+  }
+  lexer->result_symbol = HEREDOC_CONTENT;
+  return true;
+}
+
+static bool is_name_char(int32_t c) {
+  return (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') ||
+         (c >= '0' && c <= '9') || c == '_';
+}
+
+// Scan the name in `a=1`, `a+=1` or `a[i]=1`. Anything else is left to the
+// grammar, where `CC=gcc` after a command name is a word.
+static bool scan_variable_name(TSLexer *lexer) {
+  while (is_name_char(lexer->lookahead)) {
+    advance(lexer);
+  }
+  lexer->mark_end(lexer);
+  if (lexer->lookahead == '+') {
+    advance(lexer);
+  }
+  if (lexer->lookahead != '=' && lexer->lookahead != '[') {
+    return false;
+  }
+  lexer->result_symbol = VARIABLE_NAME;
+  return true;
+}
+
+// Whether `c` can start a part of a word that follows another part, as the
+// " and the bar do in foo"$x"bar
+static bool starts_word_part(int32_t c) {
+  return (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') ||
+         (c >= '0' && c <= '9') ||
+         c == '_' || c == '-' || c == '.' || c == '/' || c == '*' || c == '?' ||
+         c == '"' || c == '\'' || c == '$' || c == '`';
+}
+
+// Scan the 2 in 2>/dev/null. Digits that aren't directly followed by a
+// redirect operator, as in `sleep 1 &`, are left to the grammar as a word.
+static bool scan_file_descriptor(TSLexer *lexer) {
+  while (lexer->lookahead >= '0' && lexer->lookahead <= '9') {
+    advance(lexer);
+  }
+  if (lexer->lookahead != '<' && lexer->lookahead != '>') {
+    return false;
+  }
+  lexer->result_symbol = FILE_DESCRIPTOR;
+  return true;
+}
+
+// Scan content inside double-quoted string (handles escapes and substitutions)
+static bool scan_string_content(Scanner *scanner, TSLexer *lexer) {
+  bool has_content = false;
+
+  while (lexer->lookahead != 0) {
+    switch (lexer->lookahead) {
+      case '"':
+        // End of string
+        if (has_content) {
+          lexer->result_symbol = STRING_CONTENT;
+          return true;
+        }
+        return false;
+
+      case '\\':
+        // Escape sequence - consume two characters
+        advance(lexer);
+        if (lexer->lookahead != 0) {
+          advance(lexer);
+        }
+        has_content = true;
+        break;
+
+      case '$':
+      case '`':
+        // Substitution start - return what we have so far
+        if (has_content) {
+          lexer->result_symbol = STRING_CONTENT;
+          return true;
+        }
+        return false;
+
+      default:
+        advance(lexer);
+        has_content = true;
+        break;
+    }
+  }
+
+  if (has_content) {
+    lexer->result_symbol = STRING_CONTENT;
+    return true;
+  }
+
+  return false;
+}
+
+// Scan multiline string content (''' or """)
+static bool scan_multiline_string_content(Scanner *scanner, TSLexer *lexer, int32_t quote_char) {
+  bool has_content = false;
+
+  while (lexer->lookahead != 0) {
+    if (lexer->lookahead == quote_char) {
+      // Check for triple quote
+      advance(lexer);
+      if (lexer->lookahead == quote_char) {
+        advance(lexer);
+        if (lexer->lookahead == quote_char) {
+          // End of multiline string
+          if (has_content) {
+            lexer->result_symbol = MULTILINE_STRING_CONTENT;
+            return true;
+          }
+          return false;
+        }
+        // Only two quotes - continue
+        has_content = true;
+      } else {
+        // Only one quote - continue
+        has_content = true;
+      }
+    } else if (lexer->lookahead == '\\' && quote_char == '"') {
+      // Escape in double-quoted multiline
+      advance(lexer);
+      if (lexer->lookahead != 0) {
+        advance(lexer);
+      }
+      has_content = true;
+    } else if ((lexer->lookahead == '$' || lexer->lookahead == '`') && quote_char == '"') {
+      // Substitution in double-quoted multiline
+      if (has_content) {
+        lexer->result_symbol = MULTILINE_STRING_CONTENT;
+        return true;
+      }
+      return false;
+    } else {
+      advance(lexer);
+      has_content = true;
+    }
+  }
+
+  if (has_content) {
+    lexer->result_symbol = MULTILINE_STRING_CONTENT;
+    return true;
+  }
+
+  return false;
+}
+
+// Scan regex content between / /
+static bool scan_regex_content(Scanner *scanner, TSLexer *lexer) {
+  bool has_content = false;
+
+  while (lexer->lookahead != 0) {
+    switch (lexer->lookahead) {
+      case '/':
+        // End of regex
+        if (has_content) {
+          lexer->result_symbol = REGEX_CONTENT;
+          return true;
+        }
+        return false;
+
+      case '\\':
+        // Escape sequence
+        advance(lexer);
+        if (lexer->lookahead != 0) {
+          advance(lexer);
+        }
+        has_content = true;
+        break;
+
+      case '[':
+        // Character class - scan until ]
+        advance(lexer);
+        has_content = true;
+        while (lexer->lookahead != 0 && lexer->lookahead != ']') {
+          if (lexer->lookahead == '\\') {
+            advance(lexer);
+            if (lexer->lookahead != 0) {
+              advance(lexer);
+            }
+          } else {
+            advance(lexer);
+          }
+        }
+        if (lexer->lookahead == ']') {
+          advance(lexer);
+        }
+        break;
+
+      case '\n':
+        // Newline not allowed in regex without escaping
+        if (has_content) {
+          lexer->result_symbol = REGEX_CONTENT;
+          return true;
+        }
+        return false;
+
+      default:
+        advance(lexer);
+        has_content = true;
+        break;
+    }
+  }
+
+  if (has_content) {
+    lexer->result_symbol = REGEX_CONTENT;
+    return true;
+  }
+
+  return false;
+}
+
+// External scanner interface, wrapped by each dialect
+
+static void *scanner_create(void) {
+  Scanner *scanner = calloc(1, sizeof(Scanner));
+  return scanner;
+}
+
+static void scanner_destroy(void *payload) {
+  Scanner *scanner = (Scanner *)payload;
+  free(scanner);
+}
+
+static unsigned scanner_serialize(void *payload, char *buffer) {
+  Scanner *scanner = (Scanner *)payload;
+
+  unsigned size = 0;
+
+  buffer[size++] = (char)scanner->heredoc_count;
+  buffer[size++] = scanner->in_heredoc_body;
+  for (uint8_t i = 0; i < scanner->heredoc_count; i++) {
+    Heredoc *heredoc = &scanner->heredocs[i];
+    buffer[size++] = heredoc->strip_tabs;
+    buffer[size++] = heredoc->quoted;
+    buffer[size++] = (char)heredoc->delimiter_length;
+    memcpy(&buffer[size], heredoc->delimiter, heredoc->delimiter_length);
+    size += heredoc->delimiter_length;
+  }
+
+  buffer[size++] = scanner->paren_depth;
+  buffer[size++] = scanner->brace_depth;
+  buffer[size++] = scanner->bracket_depth;
+  buffer[size++] = scanner->in_double_quote;
+  buffer[size++] = scanner->in_single_quote;
+  buffer[size++] = scanner->in_command_sub;
+
+  return size;
+}
+
+static void scanner_deserialize(void *payload, const char *buffer, unsigned length) {
+  Scanner *scanner = (Scanner *)payload;
+
+  if (length == 0) {
+    memset(scanner, 0, sizeof(Scanner));
+    return;
+  }
+
+  unsigned pos = 0;
+
+  scanner->heredoc_count = (uint8_t)buffer[pos++];
+  scanner->in_heredoc_body = buffer[pos++];
+  for (uint8_t i = 0; i < scanner->heredoc_count; i++) {
+    Heredoc *heredoc = &scanner->heredocs[i];
+    heredoc->strip_tabs = buffer[pos++];
+    heredoc->quoted = buffer[pos++];
+    heredoc->delimiter_length = (uint8_t)buffer[pos++];
+    memcpy(heredoc->delimiter, &buffer[pos], heredoc->delimiter_length);
+    pos += heredoc->delimiter_length;
+  }
+
+  if (pos < length) scanner->paren_depth = buffer[pos++];
+  if (pos < length) scanner->brace_depth = buffer[pos++];
+  if (pos < length) scanner->bracket_depth = buffer[pos++];
+  if (pos < length) scanner->in_double_quote = buffer[pos++];
+  if (pos < length) scanner->in_single_quote = buffer[pos++];
+  if (pos < length) scanner->in_command_sub = buffer[pos++];
+}
+
+static bool scanner_scan(
+  void *payload,
+  TSLexer *lexer,
+  const bool *valid_symbols
+) {
+  Scanner *scanner = (Scanner *)payload;
+
+  // All symbols are valid during error recovery; let the internal lexer
+  // handle it rather than matching arbitrary text as an external token.
+  if (valid_symbols[ERROR_SENTINEL]) {
+    return false;
+  }
+
+  // Extras haven't been skipped yet, so a part that starts right here has
+  // no space before it and continues the current word.
+  if (valid_symbols[CONCAT] && !scanner->in_heredoc_body &&
+      starts_word_part(lexer->lookahead)) {
+    lexer->result_symbol = CONCAT;
+    return true;
+  }
+
+  // Text of the heredoc body we're in
+  if (scanner->in_heredoc_body) {
+    if (valid_symbols[HEREDOC_CONTENT] || valid_symbols[HEREDOC_END]) {
+      return scan_heredoc_content(scanner, lexer);
+    }
+  } else if (scanner->heredoc_count > 0 && valid_symbols[HEREDOC_BODY_START]) {
+    // Bodies are extras, so this is checked wherever a line can end
+    if (scan_heredoc_body_start(scanner, lexer)) {
+      return true;
+    }
+  }
+
+  // Skip whitespace
+  while (is_space(lexer->lookahead)) {
+    skip(lexer);
+  }
+
+  // Check for a heredoc delimiter after << or <<-
+  if (valid_symbols[HEREDOC_START] || valid_symbols[STRIPPED_HEREDOC_START]) {
+    return scan_heredoc_start(scanner, lexer, !valid_symbols[HEREDOC_START]);
+  }
+
+  if (valid_symbols[FILE_DESCRIPTOR] &&
+      lexer->lookahead >= '0' && lexer->lookahead <= '9') {
+    return scan_file_descriptor(lexer);
+  }
+
+  // Blank lines before an assignment that starts a statement are
+  // whitespace, but the newline after `local x` ends the command.
+  if (valid_symbols[VARIABLE_NAME] && !valid_symbols[NEWLINE]) {
+    while (is_space(lexer->lookahead) || is_newline(lexer->lookahead)) {
+      skip(lexer);
+    }
+  }
+
+  if (valid_symbols[VARIABLE_NAME] && is_name_char(lexer->lookahead) &&
+      !(lexer->lookahead >= '0' && lexer->lookahead <= '9')) {
+    return scan_variable_name(lexer);
+  }
+
+  // String content scanning
+  if (valid_symbols[STRING_CONTENT]) {
+    return scan_string_content(scanner, lexer);
+  }
+
+  // Multiline string content
+  if (valid_symbols[MULTILINE_STRING_CONTENT]) {
+    if (lexer->lookahead == '\'') {
+      return scan_multiline_string_content(scanner, lexer, '\'');
+    } else if (lexer->lookahead == '"') {
+      return scan_multiline_string_content(scanner, lexer, '"');
+    }
+  }
+
+  // Regex content
+  if (valid_symbols[REGEX_CONTENT]) {
+    return scan_regex_content(scanner, lexer);
+  }
+
+  return false;
+}
+
+#endif  // TREE_SITTER_YSH_SCANNER_H_
@@ -10,6 +10,8 @@
 /// <reference types="tree-sitter-cli/dsl" />
 // @ts-check
 
+const {simpleCommand, statements} = require('./common/rules');
+
 // Operator precedence levels (higher = tighter binding)
 const PREC = {
   LOWEST: 0,
@@ -36,6 +38,19 @@ const YSH_KEYWORDS = [
   'call',
 ];
 
+// Named character classes in Eggex, from ysh/expr_to_ast.py
+const PERL_CLASSES = ['d', 'w', 'word', 's'];
+const POSIX_CLASSES = [
+  'alnum', 'cntrl', 'lower', 'space', 'alpha', 'digit',
+  'print', 'upper', 'blank', 'graph', 'punct', 'xdigit',
+];
+
+// Unit suffixes on integers, e.g. 100 M or 4 Ki
+const NUMBER_UNITS = ['K', 'Ki', 'M', 'Mi', 'G', 'Gi', 'T', 'Ti'];
+
+// Assignment builtins that take `name=value` arguments, from OSH
+const DECLARATION_KEYWORDS = ['declare', 'typeset', 'local', 'readonly', 'export'];
+
 // Shell keywords
 const SHELL_KEYWORDS = [
   'if', 'then', 'else', 'elif', 'fi',
@@ -63,13 +78,21 @@ module.exports = grammar({
   // Tokens handled by external scanner (src/scanner.c)
   externals: $ => [
     $._heredoc_start,
-    $._heredoc_body,
+    $._stripped_heredoc_start,
+    $._heredoc_body_start,
+    $._heredoc_content,
     $._heredoc_end,
     $._string_content,
     $._multiline_string_content,
     $._regex_content,
     $._command_substitution_start,
     $._brace_expansion,
+    $.file_descriptor,
+    $._concat,
+    $._variable_name,
+    // Never produced by the scanner; listing it tells the scanner whether a
+    // newline would end a statement.
+    '\n',
     $.error_sentinel,
   ],
 
@@ -78,14 +101,15 @@ module.exports = grammar({
     /\s/,
     $.comment,
     $.line_continuation,
+    // The scanner starts a body at the first newline after a heredoc
+    // redirect, wherever in the tree that newline falls.
+    $.heredoc_body,
   ],
 
   // Handle conflicts
   conflicts: $ => [
-    [$.simple_command, $.variable_assignment],
-    [$.command_name, $.word],
-    [$._expression, $.command_substitution],
-    [$.proc_definition, $.func_definition],
+    // f () { } or p (x), decided by what follows the `(`
+    [$.function_definition, $._word_part],
   ],
 
   // Word boundaries
@@ -101,19 +125,28 @@ module.exports = grammar({
     // =========================================================================
     // Program (entry point)
     // =========================================================================
-    source_file: $ => repeat($._statement),
+    source_file: $ => statements($),
 
     _statement: $ => choice(
       $._command,
       $._ysh_statement,
     ),
 
+    // Newlines are only significant where a statement can end; everywhere
+    // else they are skipped as whitespace, like the \r of a \r\n.
+    _terminator: $ => choice(';', '\n'),
+
+    // cmd &
+    background_command: $ => seq($._command, '&'),
+
     // =========================================================================
     // Comments and Line Continuation
     // =========================================================================
     comment: $ => token(seq('#', /.*/)),
 
-    doc_comment: $ => token(seq('###', /.*/)),
+    // Wins over `comment` where it is valid, i.e. at the top of a proc or
+    // func body.
+    doc_comment: $ => token(prec(1, seq('###', /.*/))),
 
     line_continuation: $ => token(seq('\\', /\r?\n/)),
 
@@ -121,10 +154,14 @@ module.exports = grammar({
     // Commands
     // =========================================================================
     _command: $ => choice(
-      $.simple_command,
+      $._pipeline_element,
       $.pipeline,
+      $.negated_command,
       $.and_or,
-      $.subshell,
+    ),
+
+    _pipeline_element: $ => choice(
+      $.simple_command,
       $.brace_group,
       $.if_statement,
       $.for_statement,
@@ -132,96 +169,184 @@ module.exports = grammar({
       $.case_statement,
       $.function_definition,
       $.redirect_statement,
+      $.control_flow,
+      $.test_command,
+      $.arithmetic_command,
+      $.declaration_command,
     ),
 
-    simple_command: $ => seq(
-      repeat($.variable_assignment),
-      optional($.command_name),
-      repeat(choice(
-        $.word,
-        $._expression_word,
-        $.redirect,
-      )),
+    simple_command: $ => simpleCommand($, $._command_argument, yshArguments($, true)),
+
+    // Conditions like `if test -f x { ... }` end at the brace, so commands
+    // there take no block argument. They repeat their own argument rule;
+    // sharing the repeat would let the parser merge states and drop block
+    // arguments everywhere.
+    _blockless_simple_command: $ => simpleCommand(
+      $,
+      $._condition_command_argument,
+      yshArguments($, false),
+    ),
+
+    _command_argument: $ => choice(
+      field('argument', $.word),
+      field('redirect', $.redirect),
+    ),
+
+    _condition_command_argument: $ => choice(
+      field('argument', $.word),
+      field('redirect', $.redirect),
     ),
 
     command_name: $ => $.word,
 
-    pipeline: $ => prec.left(seq(
-      $._command,
-      repeat1(seq(
-        choice('|', '|&'),
-        $._command,
-      )),
-    )),
+    // p (x, y; n=1; block), see `arglist3` in ysh/grammar.pgen2
+    typed_arguments: $ => seq(
+      '(',
+      optional(alias($._proc_argument_list, $.argument_list)),
+      ')',
+    ),
 
-    and_or: $ => prec.left(PREC.AND, seq(
-      $._command,
-      repeat1(seq(
-        choice('&&', '||'),
-        $._command,
-      )),
+    // p [x > 0]
+    lazy_arguments: $ => seq('[', optional($.argument_list), ']'),
+
+    // cd /tmp { pwd }, or a Hay block like Package cpython { version = '3.12' }
+    block_argument: $ => seq('{', statements($, null, $._block_statement), '}'),
+
+    _block_statement: $ => choice(
+      $._statement,
+      $.hay_attribute,
+    ),
+
+    // version = '3.12', the bare assignment Hay blocks use for attributes
+    hay_attribute: $ => prec.right(seq(
+      field('name', $.identifier),
+      '=',
+      field('value', $._testlist),
     )),
 
-    subshell: $ => seq('(', repeat($._statement), ')'),
+    pipeline: $ => pipeline($._pipeline_element),
 
-    brace_group: $ => seq('{', repeat($._statement), '}'),
+    // ! cmd, which negates the exit status of a whole pipeline
+    negated_command: $ => seq('!', choice($._pipeline_element, $.pipeline)),
 
-    redirect_statement: $ => seq(
-      $._command,
-      repeat1($.redirect),
-    ),
+    and_or: $ => andOr($, $._pipeline_element, choice($.pipeline, $.negated_command)),
+
+    brace_group: $ => seq('{', statements($), '}'),
+
+    // Redirects on a simple command are part of the command itself; this
+    // node covers compound commands like `{ ... } > out.txt`.
+    redirect_statement: $ => prec(1, seq(
+      field('body', choice(
+        $.brace_group,
+        $.if_statement,
+        $.for_statement,
+        $.while_statement,
+        $.case_statement,
+      )),
+      repeat1(field('redirect', $.redirect)),
+    )),
 
     // =========================================================================
     // Control Flow
     // =========================================================================
-    if_statement: $ => seq(
-      'if',
-      $._condition,
-      choice(
-        // YSH style: if (expr) { }
-        seq('{', repeat($._statement), '}'),
-        // Shell style: if cmd; then ... fi
-        seq('then', repeat($._statement)),
+    if_statement: $ => choice(
+      // YSH style: if (expr) { } elif (expr) { } else { }
+      prec.right(seq(
+        'if',
+        field('condition', $._condition),
+        field('consequence', $.brace_group),
+        repeat(field('alternative', $.elif_clause)),
+        optional(field('alternative', $.else_clause)),
+      )),
+      // Shell style: if cmd; then ... fi
+      seq(
+        'if',
+        field('condition', $._condition),
+        'then',
+        statements($, 'consequence'),
+        repeat(field('alternative', alias($._shell_elif_clause, $.elif_clause))),
+        optional(field('alternative', alias($._shell_else_clause, $.else_clause))),
+        'fi',
       ),
-      repeat($.elif_clause),
-      optional($.else_clause),
-      optional('fi'),
     ),
 
     elif_clause: $ => seq(
       'elif',
-      $._condition,
-      choice(
-        seq('{', repeat($._statement), '}'),
-        seq('then', repeat($._statement)),
-      ),
+      field('condition', $._condition),
+      field('consequence', $.brace_group),
+    ),
+
+    _shell_elif_clause: $ => seq(
+      'elif',
+      field('condition', $._condition),
+      'then',
+      statements($, 'consequence'),
     ),
 
     else_clause: $ => seq(
       'else',
-      choice(
-        seq('{', repeat($._statement), '}'),
-        repeat($._statement),
-      ),
+      field('body', $.brace_group),
+    ),
+
+    _shell_else_clause: $ => seq(
+      'else',
+      statements($, 'body'),
     ),
 
     _condition: $ => choice(
       // YSH expression condition: (expr)
       $.parenthesized_expression,
       // Shell command condition
-      seq(repeat1($._command), optional(';')),
+      seq($._condition_command, optional($._terminator)),
+    ),
+
+    _condition_command: $ => choice(
+      $._condition_element,
+      alias($._condition_pipeline, $.pipeline),
+      alias($._condition_negated_command, $.negated_command),
+      alias($._condition_and_or, $.and_or),
+    ),
+
+    _condition_element: $ => choice(
+      alias($._blockless_simple_command, $.simple_command),
+      $.test_command,
+      $.arithmetic_command,
+      $.brace_group,
+      $.if_statement,
+      $.for_statement,
+      $.while_statement,
+      $.case_statement,
+      $.function_definition,
+      $.redirect_statement,
+    ),
+
+    _condition_pipeline: $ => pipeline($._condition_element),
+
+    _condition_negated_command: $ => seq(
+      '!',
+      choice($._condition_element, alias($._condition_pipeline, $.pipeline)),
+    ),
+
+    _condition_and_or: $ => andOr(
+      $,
+      $._condition_element,
+      choice(
+        alias($._condition_pipeline, $.pipeline),
+        alias($._condition_negated_command, $.negated_command),
+      ),
     ),
 
     for_statement: $ => seq(
       'for',
-      $.identifier,
-      optional(seq('in', $._for_iterable)),
-      optional(';'),
+      // for i, item in (list) { }
+      commaSep1(field('variable', $.identifier)),
+      optional(seq('in', field('iterable', $._for_iterable))),
+      optional($._terminator),
       choice(
         // YSH style
-        seq('{', repeat($._statement), '}'),
+        field('body', $.brace_group),
         // Shell style
-        seq('do', repeat($._statement), 'done'),
+        seq('do', statements($, 'body'), 'done'),
       ),
     ),
 
@@ -233,37 +358,62 @@ module.exports = grammar({
     ),
 
     while_statement: $ => seq(
-      choice('while', 'until'),
-      $._condition,
+      field('keyword', choice('while', 'until')),
+      field('condition', $._condition),
       choice(
-        seq('{', repeat($._statement), '}'),
-        seq('do', repeat($._statement), 'done'),
+        field('body', $.brace_group),
+        seq('do', statements($, 'body'), 'done'),
       ),
     ),
 
-    case_statement: $ => seq(
-      'case',
-      choice(
-        // YSH: case (expr) { }
-        seq($.parenthesized_expression, '{'),
-        // Shell: case $x in
-        seq($.word, 'in'),
+    case_statement: $ => choice(
+      // YSH: case (expr) { }
+      seq(
+        'case',
+        field('value', $.parenthesized_expression),
+        '{',
+        repeat($.case_arm),
+        '}',
+      ),
+      // Shell: case $x in ... esac
+      seq(
+        'case',
+        field('value', $.word),
+        'in',
+        repeat(alias($._shell_case_arm, $.case_arm)),
+        'esac',
       ),
-      repeat($.case_arm),
-      choice('}', 'esac'),
     ),
 
+    // Arms of a YSH case, like `ysh_case_pat` in ysh/grammar.pgen2:
+    // words and globs, (1) | (2), /eggex/ or (else)
     case_arm: $ => seq(
-      optional('('),
-      $.pattern_list,
-      choice(
-        // YSH style
-        seq('{', repeat($._statement), '}'),
-        // Shell style
-        seq(')', repeat($._statement), optional(choice(';;', ';&', ';;&'))),
-      ),
+      field('pattern', choice(
+        $.pattern_list,
+        $.expression_pattern_list,
+        $.eggex,
+        $.else_pattern,
+      )),
+      field('body', $.brace_group),
+    ),
+
+    expression_pattern_list: $ => seq(
+      $.expression_pattern,
+      repeat(seq('|', $.expression_pattern)),
     ),
 
+    expression_pattern: $ => seq('(', field('value', $._expression), ')'),
+
+    else_pattern: $ => seq('(', 'else', ')'),
+
+    _shell_case_arm: $ => prec.right(seq(
+      optional('('),
+      field('pattern', $.pattern_list),
+      ')',
+      statements($, 'body'),
+      optional(field('terminator', choice(';;', ';&', ';;&'))),
+    )),
+
     pattern_list: $ => seq(
       $.pattern,
       repeat(seq('|', $.pattern)),
@@ -275,19 +425,193 @@ module.exports = grammar({
       '*',  // default case
     ),
 
-    glob_pattern: $ => /[*?]+[^\s]*/,
+    // *.txt, but not the `)` or `|` after it in `*.txt|*.md)`
+    glob_pattern: $ => /[*?]+[^\s|)]*/,
+
+    // break, return 3 or return (x + 1). Oils parses these statically, so a
+    // typed `return (expr)` in a func is a different node from a word.
+    control_flow: $ => prec.right(seq(
+      field('keyword', choice(...CONTROL_FLOW)),
+      optional(field('argument', choice($.word, $.parenthesized_expression))),
+    )),
 
     // =========================================================================
     // Function Definition (Shell style)
     // =========================================================================
     function_definition: $ => choice(
-      // function keyword style
+      // function keyword style: function f { }
       seq(
-        optional('function'),
-        $.identifier,
+        'function',
+        field('name', $.identifier),
         optional(seq('(', ')')),
-        $.brace_group,
+        field('body', $.brace_group),
       ),
+      // POSIX style: f() { }, which wins over a command `f ()` with an empty
+      // typed argument group and a block
+      prec.dynamic(1, seq(
+        field('name', $.identifier),
+        '(',
+        ')',
+        field('body', $.brace_group),
+      )),
+    ),
+
+    // =========================================================================
+    // OSH Compatibility
+    // =========================================================================
+    // [[ -f $file && $x == y* ]]
+    test_command: $ => seq('[[', $._test_expression, ']]'),
+
+    _test_expression: $ => choice(
+      $.word,
+      $.unary_test,
+      $.binary_test,
+      alias($._parenthesized_test, $.parenthesized_expression),
+    ),
+
+    // -f file or ! expr
+    unary_test: $ => prec(PREC.NOT, seq(
+      field('operator', choice($.test_operator, '!')),
+      field('argument', $._test_expression),
+    )),
+
+    // The right side of == and != may be a glob, and of =~ a regex
+    binary_test: $ => {
+      const table = [
+        [PREC.COMPARE, choice('==', '!=', '='), choice(
+          $._test_expression,
+          alias($._test_glob, $.glob_pattern),
+        )],
+        [PREC.COMPARE, '=~', choice($._test_expression, $.regex_pattern)],
+        [PREC.COMPARE, choice('<', '>', $.test_operator), $._test_expression],
+        [PREC.AND, '&&', $._test_expression],
+        [PREC.OR, '||', $._test_expression],
+      ];
+
+      return choice(...table.map(([precedence, operator, right]) => prec.left(precedence, seq(
+        field('left', $._test_expression),
+        field('operator', operator),
+        field('right', right),
+      ))));
+    },
+
+    // y*, *.txt or [abc]?, a word with at least one glob character
+    _test_glob: $ => {
+      const plain = /[^\s&|()<>;'"$`\\\[\]*?]/.source;
+      const glob = /[*?]|\[[^\]\s]+\]/.source;
+      return token(new RegExp(`(${plain}|${glob})*(${glob})(${plain}|${glob})*`));
+    },
+
+    // ^[0-9]+$ or foo$ after =~. It only matches text with a regex operator
+    // or an anchoring $ in it, so a plain word or $var stays a word, but
+    // otherwise wins over the shell words that could lex a prefix of it.
+    regex_pattern: $ => {
+      const any = /[^\s'"\\]|\\./.source;
+      const operator = /[^\s'"\\$a-zA-Z0-9_\-\.\/{}]|\\./.source;
+      const plain = /[^\s'"\\$]/.source;
+      return token(prec(1, choice(
+        new RegExp(`(${any})*(${operator})(${any})*`),
+        new RegExp(`(${plain})+\\$(${any})*`),
+      )));
+    },
+
+    _parenthesized_test: $ => seq('(', $._test_expression, ')'),
+
+    // -f, -eq, -nt and friends; wins over a bare word where both are valid
+    test_operator: $ => token(prec(1, /-[a-zA-Z]+/)),
+
+    // (( i++ )). Like the Oils lexer, `((` at the start of a command is
+    // always arithmetic, so write `if ((x) or y)` as `if (x or y)`.
+    arithmetic_command: $ => seq('((', $._arithmetic_expression, '))'),
+
+    // $(( x + 1 ))
+    arithmetic_expansion: $ => seq('$((', $._arithmetic_expression, '))'),
+
+    // C-style arithmetic, which reuses the YSH expression node names
+    _arithmetic_expression: $ => choice(
+      $.number,
+      $.identifier,
+      $.variable_substitution,
+      $.command_substitution,
+      $.arithmetic_expansion,
+      alias($._arithmetic_subscript, $.subscript_expression),
+      alias($._arithmetic_parenthesized, $.parenthesized_expression),
+      alias($._arithmetic_unary, $.unary_expression),
+      $.postfix_expression,
+      alias($._arithmetic_binary, $.binary_expression),
+      alias($._arithmetic_ternary, $.ternary_expression),
+    ),
+
+    _arithmetic_subscript: $ => seq(
+      field('object', $.identifier),
+      token.immediate('['),
+      field('index', $._arithmetic_expression),
+      ']',
+    ),
+
+    _arithmetic_parenthesized: $ => seq('(', $._arithmetic_expression, ')'),
+
+    _arithmetic_unary: $ => prec(PREC.UNARY, seq(
+      field('operator', choice('-', '+', '~', '!', '++', '--')),
+      field('argument', $._arithmetic_expression),
+    )),
+
+    // i++ or i--
+    postfix_expression: $ => prec(PREC.POSTFIX, seq(
+      field('argument', $._arithmetic_expression),
+      field('operator', choice('++', '--')),
+    )),
+
+    _arithmetic_binary: $ => {
+      const table = [
+        [prec.right, PREC.LOWEST, choice(
+          '=', '+=', '-=', '*=', '/=', '%=', '**=',
+          '<<=', '>>=', '&=', '|=', '^=',
+        )],
+        [prec.left, PREC.OR, '||'],
+        [prec.left, PREC.AND, '&&'],
+        [prec.left, PREC.BITOR, '|'],
+        [prec.left, PREC.BITXOR, '^'],
+        [prec.left, PREC.BITAND, '&'],
+        [prec.left, PREC.COMPARE, choice('==', '!=', '<', '>', '<=', '>=')],
+        [prec.left, PREC.SHIFT, choice('<<', '>>')],
+        [prec.left, PREC.ADD, choice('+', '-')],
+        [prec.left, PREC.MUL, choice('*', '/', '%')],
+        [prec.right, PREC.POWER, '**'],
+      ];
+
+      return choice(...table.map(([assoc, precedence, operator]) => assoc(precedence, seq(
+        field('left', $._arithmetic_expression),
+        field('operator', operator),
+        field('right', $._arithmetic_expression),
+      ))));
+    },
+
+    _arithmetic_ternary: $ => prec.right(PREC.OR, seq(
+      field('condition', $._arithmetic_expression),
+      '?',
+      field('consequence', $._arithmetic_expression),
+      ':',
+      field('alternative', $._arithmetic_expression),
+    )),
+
+    // local -r x=1 y, where assignments are nodes rather than words
+    declaration_command: $ => prec.right(seq(
+      field('keyword', choice(...DECLARATION_KEYWORDS)),
+      repeat(choice(
+        field('argument', $.word),
+        $.variable_assignment,
+      )),
+    )),
+
+    // a=(1 2 3)
+    array: $ => seq(token.immediate('('), repeat($.word), ')'),
+
+    // <(cmd) or >(cmd)
+    process_substitution: $ => seq(
+      choice('<(', '>('),
+      statements($),
+      ')',
     ),
 
     // =========================================================================
@@ -304,34 +628,47 @@ module.exports = grammar({
       $.expression_statement,
     ),
 
-    var_declaration: $ => seq(
+    // var a, b: Int = 1, 2, see `ysh_var_decl` in ysh/grammar.pgen2
+    var_declaration: $ => prec.right(seq(
       'var',
-      $.identifier,
-      optional($.type_annotation),
-      optional(seq('=', $._expression)),
-    ),
+      nameTypeList($),
+      optional(seq('=', field('value', $._testlist))),
+    )),
 
-    const_declaration: $ => seq(
+    const_declaration: $ => prec.right(seq(
       'const',
-      $.identifier,
-      optional($.type_annotation),
+      nameTypeList($),
       '=',
-      $._expression,
-    ),
+      field('value', $._testlist),
+    )),
 
-    setvar_statement: $ => seq(
+    // setvar x, y = y, x, see `ysh_mutation` in ysh/grammar.pgen2
+    setvar_statement: $ => prec.right(seq(
       'setvar',
-      $._lvalue,
-      $._assignment_op,
-      $._expression,
-    ),
+      commaSep1(field('left', $._lvalue)),
+      field('operator', $._assignment_op),
+      field('right', $._testlist),
+    )),
 
-    setglobal_statement: $ => seq(
+    setglobal_statement: $ => prec.right(seq(
       'setglobal',
-      $._lvalue,
-      $._assignment_op,
+      commaSep1(field('left', $._lvalue)),
+      field('operator', $._assignment_op),
+      field('right', $._testlist),
+    )),
+
+    // The right-hand side of a declaration or mutation, where `1, 2` is a
+    // tuple.
+    _testlist: $ => prec.right(choice(
       $._expression,
-    ),
+      $.expression_list,
+    )),
+
+    expression_list: $ => prec.right(seq(
+      $._expression,
+      repeat1(prec.right(seq(',', $._expression))),
+      optional(','),
+    )),
 
     _lvalue: $ => choice(
       $.identifier,
@@ -344,65 +681,98 @@ module.exports = grammar({
       '<<=', '>>=', '&=', '|=', '^=',
     ),
 
-    type_annotation: $ => seq(':', $.type_expression),
+    type_annotation: $ => seq(':', field('type', $.type_expression)),
 
     type_expression: $ => seq(
-      $.identifier,
-      optional(seq('[', commaSep1($.type_expression), ']')),
+      field('name', $.identifier),
+      optional(seq('[', commaSep1(field('parameter', $.type_expression)), ']')),
     ),
 
     proc_definition: $ => seq(
       'proc',
-      $.identifier,
-      optional($.proc_signature),
-      $.brace_group,
+      field('name', $.identifier),
+      optional(field('params', $.proc_signature)),
+      field('body', alias($._documented_body, $.brace_group)),
     ),
 
-    proc_signature: $ => choice(
-      // Open proc: proc p { }
-      seq('(', ')'),
-      // Closed proc with params: proc p (x, y; z) { }
-      seq('(', optional($.param_list), ')'),
+    // proc p (word; typed; named; block) { }, where every group is optional.
+    proc_signature: $ => seq(
+      '(',
+      optional(field('word', $.word_params)),
+      optional(seq(
+        ';',
+        optional(field('typed', $.typed_params)),
+        optional(seq(
+          ';',
+          optional(field('named', $.named_params)),
+          optional(seq(
+            ';',
+            optional(field('block', $.block_params)),
+          )),
+        )),
+      )),
+      ')',
     ),
 
+    word_params: $ => paramGroup($, $.param),
+
+    typed_params: $ => paramGroup($, $.param),
+
+    named_params: $ => paramGroup($, $.named_param),
+
+    block_params: $ => paramGroup($, $.param),
+
     func_definition: $ => seq(
       'func',
-      $.identifier,
+      field('name', $.identifier),
       '(',
-      optional($.param_list),
+      optional(field('params', $.param_list)),
       ')',
-      optional(seq(':', $.type_expression)),
-      $.brace_group,
+      optional(field('return_type', $.return_type)),
+      field('body', alias($._documented_body, $.brace_group)),
     ),
 
-    param_list: $ => seq(
-      commaSep1($.param),
-      optional(seq(';', commaSep1($.named_param))),
+    // The body of a proc or func, which may start with ### docstring lines.
+    _documented_body: $ => seq(
+      '{',
+      repeat($.doc_comment),
+      statements($),
+      '}',
+    ),
+
+    return_type: $ => seq('=>', field('type', $.type_expression)),
+
+    param_list: $ => choice(
+      seq(
+        paramGroup($, $.param),
+        optional(seq(';', optional(paramGroup($, $.named_param)))),
+      ),
+      seq(';', paramGroup($, $.named_param)),
     ),
 
     param: $ => seq(
-      $.identifier,
-      optional($.type_annotation),
-      optional(seq('=', $._expression)),
+      field('name', $.identifier),
+      optional(field('type', $.type_annotation)),
+      optional(seq('=', field('value', $._expression))),
     ),
 
     named_param: $ => seq(
-      $.identifier,
-      optional($.type_annotation),
-      optional(seq('=', $._expression)),
+      field('name', $.identifier),
+      optional(field('type', $.type_annotation)),
+      optional(seq('=', field('value', $._expression))),
     ),
 
-    rest_param: $ => seq('...', $.identifier),
+    rest_param: $ => seq('...', field('name', $.identifier)),
 
-    call_expression_statement: $ => seq(
+    call_expression_statement: $ => prec.right(seq(
       'call',
-      $._expression,
-    ),
+      field('expression', $._expression),
+    )),
 
-    expression_statement: $ => seq(
+    expression_statement: $ => prec.right(seq(
       '=',
-      $._expression,
-    ),
+      field('expression', $._expression),
+    )),
 
     // =========================================================================
     // Expressions
@@ -414,22 +784,32 @@ module.exports = grammar({
       $.ternary_expression,
       $.comparison_expression,
       $.range_expression,
+      $.lambda_expression,
     ),
 
     _primary_expression: $ => choice(
       $.identifier,
       $.number,
+      $.number_with_unit,
+      $.char_literal,
       $.string,
       $.list_literal,
+      $.list_comprehension,
       $.dict_literal,
+      $.dict_comprehension,
       $.parenthesized_expression,
       $.call_expression,
       $.subscript_expression,
       $.attribute_expression,
       $.command_substitution,
+      $.split_command_substitution,
       $.variable_substitution,
       $.expression_substitution,
       $.array_splice,
+      $.expression_literal,
+      $.command_literal,
+      $.place_reference,
+      $.word_array,
       $.eggex,
       $.null_literal,
       $.boolean_literal,
@@ -437,71 +817,82 @@ module.exports = grammar({
 
     parenthesized_expression: $ => seq('(', $._expression, ')'),
 
-    unary_expression: $ => prec(PREC.UNARY, choice(
-      seq('-', $._expression),
-      seq('+', $._expression),
-      seq('~', $._expression),
-      seq('not', $._expression),
-      seq('!', $._expression),
-    )),
-
-    binary_expression: $ => choice(
-      // Arithmetic
-      prec.left(PREC.ADD, seq($._expression, choice('+', '-'), $._expression)),
-      prec.left(PREC.MUL, seq($._expression, choice('*', '/', '//', '%'), $._expression)),
-      prec.right(PREC.POWER, seq($._expression, '**', $._expression)),
-      // String concatenation
-      prec.left(PREC.ADD, seq($._expression, '++', $._expression)),
-      // Bitwise
-      prec.left(PREC.BITOR, seq($._expression, '|', $._expression)),
-      prec.left(PREC.BITXOR, seq($._expression, '^', $._expression)),
-      prec.left(PREC.BITAND, seq($._expression, '&', $._expression)),
-      prec.left(PREC.SHIFT, seq($._expression, choice('<<', '>>'), $._expression)),
-      // Logical
-      prec.left(PREC.AND, seq($._expression, choice('and', '&&'), $._expression)),
-      prec.left(PREC.OR, seq($._expression, choice('or', '||'), $._expression)),
-    ),
+    unary_expression: $ => prec(PREC.UNARY, seq(
+      field('operator', choice('-', '+', '~', 'not', '!')),
+      field('argument', $._expression),
+    )),
+
+    binary_expression: $ => {
+      const table = [
+        // Arithmetic
+        [prec.left, PREC.ADD, choice('+', '-')],
+        [prec.left, PREC.MUL, choice('*', '/', '//', '%')],
+        [prec.right, PREC.POWER, '**'],
+        // String concatenation
+        [prec.left, PREC.ADD, '++'],
+        // Bitwise
+        [prec.left, PREC.BITOR, '|'],
+        [prec.left, PREC.BITXOR, '^'],
+        [prec.left, PREC.BITAND, '&'],
+        [prec.left, PREC.SHIFT, choice('<<', '>>')],
+        // Logical
+        [prec.left, PREC.AND, choice('and', '&&')],
+        [prec.left, PREC.OR, choice('or', '||')],
+      ];
+
+      return choice(...table.map(([assoc, precedence, operator]) => assoc(precedence, seq(
+        field('left', $._expression),
+        field('operator', operator),
+        field('right', $._expression),
+      ))));
+    },
 
     comparison_expression: $ => prec.left(PREC.COMPARE, seq(
-      $._expression,
-      repeat1(seq(
-        choice(
-          '===', '!==', '~==',
-          '<', '>', '<=', '>=',
-          'in', 'is',
-          seq('not', 'in'),
-          seq('is', 'not'),
-          '~', '!~', '~~', '!~~',
-        ),
-        $._expression,
+      field('left', $._expression),
+      field('operator', choice(
+        '===', '!==', '~==',
+        '<', '>', '<=', '>=',
+        'in', 'is',
+        seq('not', 'in'),
+        seq('is', 'not'),
+        '~', '!~', '~~', '!~~',
       )),
+      field('right', $._expression),
     )),
 
     ternary_expression: $ => prec.right(PREC.LOWEST, seq(
-      $._expression,
+      field('consequence', $._expression),
       'if',
-      $._expression,
+      field('condition', $._expression),
       'else',
-      $._expression,
+      field('alternative', $._expression),
+    )),
+
+    // |x, y| x + y, like lambdef in ysh/grammar.pgen2
+    lambda_expression: $ => prec.right(PREC.LOWEST, seq(
+      '|',
+      commaSep(field('parameter', $.identifier)),
+      '|',
+      field('body', $._expression),
     )),
 
     range_expression: $ => prec.left(PREC.COMPARE, seq(
-      $._expression,
-      choice('..<', '..='),
-      $._expression,
+      field('left', $._expression),
+      field('operator', choice('..<', '..=')),
+      field('right', $._expression),
     )),
 
     call_expression: $ => prec(PREC.POSTFIX, seq(
-      $._primary_expression,
+      field('function', $._primary_expression),
       '(',
-      optional($.argument_list),
+      optional(field('arguments', $.argument_list)),
       ')',
     )),
 
-    argument_list: $ => seq(
-      commaSep1($._argument),
-      optional(seq(';', commaSep1($.named_argument))),
-    ),
+    // x, y; n=1, see `arglist` in ysh/grammar.pgen2
+    argument_list: $ => argumentList($, false),
+
+    _proc_argument_list: $ => argumentList($, true),
 
     _argument: $ => choice(
       $._expression,
@@ -509,24 +900,44 @@ module.exports = grammar({
     ),
 
     named_argument: $ => seq(
-      $.identifier,
+      field('name', $.identifier),
       '=',
-      $._expression,
+      field('value', $._expression),
     ),
 
     spread_argument: $ => seq('...', $._expression),
 
+    // a[i], a[1:3] or d['k', 'j'], see `subscriptlist` in ysh/grammar.pgen2
     subscript_expression: $ => prec(PREC.POSTFIX, seq(
-      $._primary_expression,
+      field('object', $._primary_expression),
       '[',
-      $._expression,
+      commaSep1(field('index', choice($._expression, $.slice))),
+      optional(','),
       ']',
     )),
 
+    // 1:3, :n, i: or :
+    slice: $ => seq(
+      optional(field('start', $._expression)),
+      ':',
+      optional(field('end', $._expression)),
+    ),
+
     attribute_expression: $ => prec(PREC.POSTFIX, seq(
-      $._primary_expression,
-      choice('.', '->'),
-      $.identifier,
+      field('object', $._primary_expression),
+      // `=>` is the method-call trailer, as in `x => upper()`
+      field('operator', choice('.', '->', '=>')),
+      field('attribute', $.identifier),
+    )),
+
+    // &x or &d['key'], an out param for a proc
+    place_reference: $ => prec(PREC.UNARY, seq(
+      '&',
+      field('place', choice(
+        $.identifier,
+        $.subscript_expression,
+        $.attribute_expression,
+      )),
     )),
 
     // =========================================================================
@@ -554,6 +965,19 @@ module.exports = grammar({
 
     float: $ => /[0-9](_?[0-9])*(\.[0-9](_?[0-9])*)?([eE][+-]?[0-9]+)?/,
 
+    // 100 M, the `Expr_DecInt [Expr_Name]` form of `atom`
+    number_with_unit: $ => seq(
+      field('value', $.integer),
+      field('unit', alias(choice(...NUMBER_UNITS), $.unit)),
+    ),
+
+    // \n, \\, \u{3bc} or \yff as a standalone expression
+    char_literal: $ => token(choice(
+      /\\[\\'"nrt0]/,
+      /\\u\{[0-9a-fA-F]{1,6}\}/,
+      /\\y[0-9a-fA-F]{2}/,
+    )),
+
     list_literal: $ => seq(
       '[',
       optional(commaSep($._expression)),
@@ -561,17 +985,27 @@ module.exports = grammar({
       ']',
     ),
 
+    // [x * 2 for x in (items) if x > 0], see `comp_for` in ysh/grammar.pgen2
+    list_comprehension: $ => comprehension($, '[', field('body', $._expression), ']'),
+
+    // Pairs are separated by `comma_newline`. Newlines are extras here, so
+    // the comma is optional between any two pairs.
     dict_literal: $ => seq(
       '{',
-      optional(commaSep($.dict_pair)),
+      optional(seq(
+        $.dict_pair,
+        repeat(seq(optional(','), $.dict_pair)),
+      )),
       optional(','),
       '}',
     ),
 
+    dict_comprehension: $ => comprehension($, '{', field('body', $.dict_pair), '}'),
+
     dict_pair: $ => seq(
-      choice($.identifier, $.string),
+      field('key', choice($.identifier, $.string)),
       ':',
-      $._expression,
+      field('value', $._expression),
     ),
 
     // =========================================================================
@@ -584,15 +1018,14 @@ module.exports = grammar({
       $.raw_string,
       $.multiline_single_string,
       $.multiline_double_string,
-      $.j_string,
+      $.j8_string,
+      $.multiline_j8_string,
     ),
 
+    // Single quotes have no escapes, in shell and in YSH
     single_quoted_string: $ => seq(
       "'",
-      repeat(choice(
-        /[^'\\]+/,
-        $.escape_sequence,
-      )),
+      optional(/[^']+/),
       "'",
     ),
 
@@ -601,22 +1034,36 @@ module.exports = grammar({
       repeat(choice(
         /[^"\\$`]+/,
         $.escape_sequence,
+        $._double_escape,
         $.variable_substitution,
         $.command_substitution,
         $.expression_substitution,
+        $.arithmetic_expansion,
       )),
       '"',
     ),
 
+    // $'...' with bash's C escapes; a backslash before any other character
+    // is kept as is.
     dollar_single_quoted_string: $ => seq(
       "$'",
       repeat(choice(
         /[^'\\]+/,
-        $.escape_sequence,
+        alias($._c_escape, $.escape_sequence),
+        token(prec(-1, /\\./)),
       )),
       "'",
     ),
 
+    _c_escape: $ => token(choice(
+      /\\[\\'"?abefnrtv]/,
+      /\\x[0-9a-fA-F]{1,2}/,
+      /\\u[0-9a-fA-F]{1,4}/,
+      /\\U[0-9a-fA-F]{1,8}/,
+      /\\[0-7]{1,3}/,
+      /\\c./,
+    )),
+
     raw_string: $ => seq(
       "r'",
       /[^']*/,
@@ -624,7 +1071,7 @@ module.exports = grammar({
     ),
 
     multiline_single_string: $ => seq(
-      choice("'''", "u'''", "b'''", "r'''"),
+      choice("'''", "r'''"),
       repeat(choice(
         /[^']+/,
         seq("'", /[^']/),
@@ -640,6 +1087,7 @@ module.exports = grammar({
         seq('"', /[^"]/),
         seq('""', /[^"]/),
         $.escape_sequence,
+        $._double_escape,
         $.variable_substitution,
         $.command_substitution,
         $.expression_substitution,
@@ -647,15 +1095,28 @@ module.exports = grammar({
       '"""',
     ),
 
-    j_string: $ => seq(
-      'j"',
-      repeat(choice(
-        /[^"\\]+/,
-        $.escape_sequence,
-      )),
-      '"',
+    // J8 strings: u'...' is Unicode text, and b'...' may also contain
+    // arbitrary bytes like \yff.
+    j8_string: $ => choice(
+      seq("u'", repeat(choice(/[^'\\]+/, $._j8_escape)), "'"),
+      seq("b'", repeat(choice(/[^'\\]+/, $._j8_escape, $._byte_escape)), "'"),
     ),
 
+    multiline_j8_string: $ => choice(
+      seq("u'''", repeat(choice(...multilineJ8Parts($))), "'''"),
+      seq("b'''", repeat(choice(...multilineJ8Parts($), $._byte_escape)), "'''"),
+    ),
+
+    _j8_escape: $ => alias(token(choice(
+      /\\[\\'"\/bfnrt]/,
+      /\\u\{[0-9a-fA-F]{1,6}\}/,
+    )), $.escape_sequence),
+
+    _byte_escape: $ => alias(token(/\\y[0-9a-fA-F]{2}/), $.escape_sequence),
+
+    // \$ and \` only mean something inside double quotes
+    _double_escape: $ => alias(token(/\\[$`]/), $.escape_sequence),
+
     escape_sequence: $ => token(choice(
       // Single character escapes
       /\\[\\'"abefnrtv0]/,
@@ -690,17 +1151,33 @@ module.exports = grammar({
 
     braced_variable: $ => seq(
       '${',
-      optional(choice('#', '!')),  // prefix operators
-      $.identifier,
+      // Prefix operators; the # must not start a comment
+      optional(field('prefix', choice(token.immediate(prec(1, '#')), '!'))),
+      field('name', $.identifier),
       optional($._variable_operation),
       '}',
     ),
 
     _variable_operation: $ => choice(
       // Test operators: ${var:-default}
-      seq(choice(':-', '-', ':=', '=', ':?', '?', ':+', '+'), optional($.word)),
-      // String operators: ${var%pattern}
-      seq(choice('%', '%%', '#', '##'), optional($.word)),
+      seq(
+        field('operator', choice(':-', '-', ':=', '=', ':?', '?', ':+', '+')),
+        optional(field('value', $.word)),
+      ),
+      // String operators: ${var%pattern}; like the prefix, # and ## must not
+      // start a comment
+      seq(
+        field('operator', choice(
+          '%',
+          '%%',
+          token.immediate(prec(1, '#')),
+          token.immediate(prec(1, '##')),
+        )),
+        optional(field('value', choice(
+          $.word,
+          alias($._expansion_glob, $.glob_pattern),
+        ))),
+      ),
       // Substitution: ${var/pat/replace}
       seq('/', optional('/'), optional($.word), optional(seq('/', optional($.word)))),
       // Slice: ${var:offset:length}
@@ -709,15 +1186,31 @@ module.exports = grammar({
       seq('[', choice($._expression, '@', '*'), ']'),
     ),
 
+    // */ or .*, the pattern in ${path##*/}
+    _expansion_glob: $ => token(/[^\s}'"$`\\]*[*?][^\s}'"$`\\]*/),
+
     command_substitution: $ => choice(
-      seq('$(', repeat($._statement), ')'),
+      seq('$(', statements($), ')'),
       // Backtick form (deprecated but supported)
       seq('`', /[^`]*/, '`'),
     ),
 
+    // @(cmd), splits the output of cmd into words
+    split_command_substitution: $ => seq('@(', statements($), ')'),
+
+    // ^(echo hi), an unevaluated command
+    command_literal: $ => seq('^(', statements($), ')'),
+
+    // ^[x + 1], an unevaluated expression
+    expression_literal: $ => seq(
+      '^[',
+      field('expression', $._expression),
+      ']',
+    ),
+
     expression_substitution: $ => seq(
       '$[',
-      $._expression,
+      field('expression', $._expression),
       ']',
     ),
 
@@ -731,116 +1224,224 @@ module.exports = grammar({
     // =========================================================================
     // Eggex (Regular Expressions)
     // =========================================================================
+    // /d+ ; i ; ERE/, see `eggex` in ysh/grammar.pgen2
     eggex: $ => seq(
-      '/',
-      repeat($._regex_part),
-      '/',
-      optional($.regex_flags),
+      // Wins over a bare word like `/d+/` where both could start, e.g. in
+      // case arms.
+      alias(token(prec(1, '/')), '/'),
+      optional(field('pattern', $._regex)),
+      optional(seq(
+        ';',
+        optional(field('flags', $.regex_flags)),
+        optional(seq(';', field('translation', $.identifier))),
+      )),
+      alias(token(prec(1, '/')), '/'),
     ),
 
-    _regex_part: $ => choice(
-      $.regex_literal,
-      $.regex_char_class,
-      $.regex_group,
-      $.regex_quantifier,
+    _regex: $ => choice(
+      $._regex_sequence,
+      $.regex_alternation,
+    ),
+
+    regex_alternation: $ => prec.left(seq(
+      $._regex_sequence,
+      repeat1(seq(field('operator', choice('|', 'or')), $._regex_sequence)),
+    )),
+
+    _regex_sequence: $ => choice(
+      $._regex_term,
+      $.regex_sequence,
+    ),
+
+    regex_sequence: $ => seq($._regex_term, repeat1($._regex_term)),
+
+    _regex_term: $ => choice(
+      $._regex_atom,
+      $.regex_repetition,
+    ),
+
+    regex_repetition: $ => seq(
+      field('atom', $._regex_atom),
+      field('quantifier', $.regex_quantifier),
+    ),
+
+    // + * ? {3} {3,} {,4} {3,4}, optionally with a mode like {N 3,4}
+    regex_quantifier: $ => choice(
+      '+',
+      '*',
+      '?',
+      seq(
+        '{',
+        optional(field('mode', $.identifier)),
+        choice(
+          '+',
+          '*',
+          '?',
+          seq(field('min', $.integer), optional(',')),
+          seq(',', field('max', $.integer)),
+          seq(field('min', $.integer), ',', field('max', $.integer)),
+        ),
+        '}',
+      ),
+    ),
+
+    _regex_atom: $ => choice(
+      $.char_literal,
+      $.regex_named_class,
+      // A reference to another eggex, e.g. HexDigit
+      $.identifier,
       $.regex_anchor,
+      $.regex_char_class,
+      $.regex_negation,
       $.regex_splice,
+      $.single_quoted_string,
+      $.regex_group,
+      $.regex_capture,
     ),
 
-    regex_literal: $ => /[^\/\[\](){}*+?|\\^$\s]+/,
+    regex_named_class: $ => choice('dot', ...PERL_CLASSES, ...POSIX_CLASSES),
 
+    regex_anchor: $ => choice('.', '^', '$', /%[a-zA-Z_]+/),
+
+    // [a-f 0-9 '_' digit !space]
     regex_char_class: $ => seq(
       '[',
-      optional('^'),
-      repeat(choice(
-        /[^\]\\]/,
-        $.escape_sequence,
+      repeat1(choice(
+        $._regex_class_char,
         $.regex_range,
+        // d and w are literal characters inside [], only long names are classes
+        alias(choice('word', ...POSIX_CLASSES), $.regex_named_class),
+        alias($._regex_class_negation, $.regex_negation),
+        $.regex_splice,
       )),
       ']',
     ),
 
-    regex_range: $ => /.-./,
+    regex_range: $ => seq(
+      field('start', $._regex_class_char),
+      '-',
+      field('end', $._regex_class_char),
+    ),
 
-    regex_group: $ => seq(
-      choice(
-        '(',
-        seq('<', optional('capture'), optional($.identifier)),
-      ),
-      repeat($._regex_part),
-      choice(')', '>'),
+    _regex_class_char: $ => choice(
+      $.identifier,
+      $.integer,
+      $.single_quoted_string,
+      $.char_literal,
     ),
 
-    regex_quantifier: $ => choice(
-      '*',
-      '+',
-      '?',
-      seq('{', /[0-9]+/, optional(seq(',', optional(/[0-9]+/))), '}'),
+    regex_negation: $ => seq('!', choice($.regex_named_class, $.regex_char_class)),
+
+    _regex_class_negation: $ => seq(
+      '!',
+      alias(choice('word', ...POSIX_CLASSES), $.regex_named_class),
     ),
 
-    regex_anchor: $ => choice('^', '$', '%start', '%end'),
+    regex_splice: $ => seq('@', field('name', $.identifier)),
 
-    regex_splice: $ => seq('@', $.identifier),
+    regex_group: $ => seq('(', optional(field('pattern', $._regex)), ')'),
 
-    regex_flags: $ => seq(';', repeat($.identifier)),
+    // <capture d+ as year: int>
+    regex_capture: $ => seq(
+      '<',
+      'capture',
+      field('pattern', $._regex),
+      optional(seq('as', field('name', $.identifier))),
+      optional(seq(':', field('conversion', $.identifier))),
+      '>',
+    ),
+
+    regex_flags: $ => repeat1($.regex_flag),
+
+    // i, reg_icase, or !reg_newline
+    regex_flag: $ => seq(optional('!'), field('name', $.identifier)),
 
     // =========================================================================
     // Redirections
     // =========================================================================
     redirect: $ => seq(
-      optional($.file_descriptor),
+      optional(field('descriptor', $.file_descriptor)),
       choice(
         // Input redirections
-        seq('<', $.word),
-        seq('<<', $.heredoc_redirect),
-        seq('<<<', $.word),
+        seq(field('operator', '<'), field('destination', $.word)),
+        seq(
+          field('operator', '<<'),
+          field('destination', alias($._heredoc_start, $.heredoc_delimiter)),
+        ),
+        // <<- strips leading tabs from the body and delimiter lines
+        seq(
+          field('operator', '<<-'),
+          field('destination', alias($._stripped_heredoc_start, $.heredoc_delimiter)),
+        ),
+        seq(field('operator', '<<<'), field('destination', $.word)),
         // Output redirections
-        seq('>', $.word),
-        seq('>>', $.word),
-        seq('>|', $.word),
-        seq('&>', $.word),
-        seq('&>>', $.word),
+        seq(
+          field('operator', choice('>', '>>', '>|', '&>', '&>>')),
+          field('destination', $.word),
+        ),
         // Descriptor redirections
-        seq('>&', choice($.file_descriptor, '-')),
-        seq('<&', choice($.file_descriptor, '-')),
-        seq('<>', $.word),
+        seq(
+          field('operator', choice('>&', '<&')),
+          field('destination', choice(alias(/[0-9]+/, $.file_descriptor), '-')),
+        ),
+        seq(field('operator', '<>'), field('destination', $.word)),
       ),
     ),
 
-    file_descriptor: $ => /[0-9]+/,
-
-    heredoc_redirect: $ => seq(
-      optional('-'),
-      $.heredoc_delimiter,
-    ),
-
-    heredoc_delimiter: $ => choice(
-      $.identifier,
-      $.single_quoted_string,
-      $.double_quoted_string,
+    // Substitutions are only scanned when the delimiter is unquoted.
+    heredoc_body: $ => seq(
+      $._heredoc_body_start,
+      repeat(choice(
+        $._heredoc_content,
+        $.variable_substitution,
+        $.command_substitution,
+        $.expression_substitution,
+      )),
+      $._heredoc_end,
     ),
 
     // =========================================================================
     // Variable Assignment
     // =========================================================================
-    variable_assignment: $ => seq(
-      $.identifier,
-      optional(seq('[', $._expression, ']')),
-      choice('=', '+='),
-      optional($.word),
-    ),
+    // The scanner only reads a name as an assignment's when `=`, `+=` or `[`
+    // follows it, so words like CC=gcc elsewhere stay words.
+    variable_assignment: $ => prec.right(seq(
+      field('name', alias($._variable_name, $.identifier)),
+      optional(seq(token.immediate('['), field('index', $._expression), ']')),
+      // No whitespace is allowed around the operator: `x = 1` is a command.
+      field('operator', token.immediate(prec(1, choice('=', '+=')))),
+      optional(field('value', choice($.word, $.array))),
+    )),
 
     // =========================================================================
     // Words and Identifiers
     // =========================================================================
     word: $ => choice(
+      $._word_part,
+      $.concatenation,
+    ),
+
+    // foo"$x"bar, word parts with nothing between them
+    concatenation: $ => prec.left(seq(
+      $._word_part,
+      repeat1(seq($._concat, $._word_part)),
+    )),
+
+    _word_part: $ => choice(
       $.bare_word,
+      // Identifier-shaped words lex as identifiers wherever keywords are
+      // also valid, so they are folded back into bare words here.
+      alias($.identifier, $.bare_word),
       $.string,
       $.variable_substitution,
       $.command_substitution,
+      $.split_command_substitution,
       $.expression_substitution,
+      $.arithmetic_expansion,
+      $.process_substitution,
       $.brace_expansion,
       $.tilde_expansion,
+      alias($._word_glob, $.glob_pattern),
     ),
 
     _expression_word: $ => choice(
@@ -850,30 +1451,31 @@ module.exports = grammar({
       $.expression_substitution,
     ),
 
-    bare_word: $ => /[a-zA-Z0-9_\-\.\/]+/,
+    // *.txt in `rm *.txt`. A case pattern's glob_pattern wins where both
+    // are valid.
+    _word_glob: $ => token(prec(-1, /[*?][a-zA-Z0-9_\-\.\/*?]*/)),
 
     identifier: $ => /[a-zA-Z_][a-zA-Z0-9_]*/,
 
-    brace_expansion: $ => seq(
+    bare_word: $ => /[a-zA-Z0-9_\-\.\/]+/,
+
+    // Brace expansion never contains whitespace, so it is lexed as a single
+    // token; this keeps `{a,b}` apart from the `{` that opens a block.
+    brace_expansion: $ => token(seq(
       '{',
       choice(
         // Sequence: {1..10} or {a..z}
-        seq($._brace_element, '..', $._brace_element, optional(seq('..', $._brace_element))),
+        seq(/[a-zA-Z0-9_\-]+/, '..', /[a-zA-Z0-9_\-]+/, optional(seq('..', /[a-zA-Z0-9_\-]+/))),
         // Alternatives: {a,b,c}
-        seq($._brace_element, repeat1(seq(',', $._brace_element))),
+        seq(/[a-zA-Z0-9_\-$]*/, repeat1(seq(',', /[a-zA-Z0-9_\-$]*/))),
       ),
       '}',
-    ),
-
-    _brace_element: $ => choice(
-      /[a-zA-Z0-9_\-]+/,
-      $.variable_substitution,
-    ),
+    )),
 
-    tilde_expansion: $ => seq(
+    tilde_expansion: $ => token(seq(
       '~',
       optional(/[a-zA-Z_][a-zA-Z0-9_]*/),
-    ),
+    )),
 
     // =========================================================================
     // Word Array Literals
@@ -886,6 +1488,99 @@ module.exports = grammar({
   },
 });
 
+// The typed args, lazy args and (when `withBlock`) block that may follow a
+// command's words: p a (x; n=1) [y] { ... }
+function yshArguments($, withBlock) {
+  const args = [
+    optional(field('typed_arguments', $.typed_arguments)),
+    optional(field('lazy_arguments', $.lazy_arguments)),
+  ];
+  if (withBlock) {
+    args.push(optional(field('block', $.block_argument)));
+  }
+  return args;
+}
+
+function pipeline(element) {
+  return prec.left(seq(
+    element,
+    repeat1(seq(
+      field('operator', choice('|', '|&')),
+      element,
+    )),
+  ));
+}
+
+function andOr($, element, pipeline) {
+  return prec.left(PREC.AND, seq(
+    choice(element, pipeline),
+    repeat1(seq(
+      field('operator', choice('&&', '||')),
+      choice(element, pipeline),
+    )),
+  ));
+}
+
+// A comma-separated parameter group that may end in `...rest` and a trailing
+// comma, like `param_group` in ysh/grammar.pgen2.
+function paramGroup($, param) {
+  return choice(
+    seq(commaSep1(param), optional(seq(',', $.rest_param)), optional(',')),
+    seq($.rest_param, optional(',')),
+  );
+}
+
+// Positional arguments, then named ones after `;`, either group possibly
+// empty. Procs take a third `; block` group.
+function argumentList($, withBlock) {
+  let named = seq(
+    ';',
+    optional(seq(commaSep1(choice($.named_argument, $.spread_argument)), optional(','))),
+  );
+  if (withBlock) {
+    named = seq(named, optional(seq(';', optional(field('block', $._expression)))));
+  }
+  return choice(
+    seq(commaSep1($._argument), optional(','), optional(named)),
+    named,
+  );
+}
+
+// The `for name, ... in iterable [if condition]` clause of a comprehension,
+// between its brackets.
+function comprehension($, open, body, close) {
+  return seq(
+    open,
+    body,
+    'for',
+    commaSep1(field('variable', $.identifier)),
+    'in',
+    field('iterable', $._expression),
+    optional(seq('if', field('condition', $._expression))),
+    close,
+  );
+}
+
+// The names of a `var` or `const`, each with an optional type:
+// a, b: Int, c
+function nameTypeList($) {
+  return commaSep1(seq(
+    field('name', $.identifier),
+    optional(field('type', $.type_annotation)),
+  ));
+}
+
+// The text and escapes of a u''' or b''' string, which may contain
+// single quotes but not three in a row
+function multilineJ8Parts($) {
+  return [
+    /[^'\\]+/,
+    seq("'", /[^'\\]/),
+    seq("''", /[^'\\]/),
+    $._j8_escape,
+  ];
+}
+
 // Helper function for comma-separated lists
 function commaSep(rule) {
   return optional(commaSep1(rule));
@@ -0,0 +1,84 @@
+/// <reference types="tree-sitter-cli/dsl" />
+// @ts-check
+
+// OSH, the bash-compatible dialect of Oils. It shares the YSH lexer and
+// command grammar, but has no expression mode: `var`, `proc` and friends are
+// plain command names, conditions are always commands, and words have no
+// `$[expr]` or `@(cmd)`.
+
+const YSH = require('../grammar');
+const {simpleCommand, statements} = require('../common/rules');
+
+module.exports = grammar(YSH, {
+  name: 'osh',
+
+  rules: {
+    _statement: $ => $._command,
+
+    // No typed, lazy or block arguments
+    simple_command: $ => simpleCommand($, $._command_argument),
+
+    _blockless_simple_command: $ => simpleCommand($, $._condition_command_argument),
+
+    // `if (cd /tmp; ls)` is a subshell, not an expression
+    _condition: $ => seq($._condition_command, optional($._terminator)),
+
+    _pipeline_element: ($, original) => choice(
+      original,
+      $.subshell,
+      $.c_style_for_statement,
+    ),
+
+    _condition_element: ($, original) => choice(
+      original,
+      $.subshell,
+      $.c_style_for_statement,
+    ),
+
+    // (cd /tmp; ls)
+    subshell: $ => seq('(', statements($), ')'),
+
+    // for ((i = 0; i < n; i++)); do ...; done
+    c_style_for_statement: $ => seq(
+      'for',
+      '((',
+      optional(field('initializer', $._arithmetic_expression)),
+      ';',
+      optional(field('condition', $._arithmetic_expression)),
+      ';',
+      optional(field('update', $._arithmetic_expression)),
+      '))',
+      optional($._terminator),
+      choice(
+        field('body', $.brace_group),
+        seq('do', statements($, 'body'), 'done'),
+      ),
+    ),
+
+    // Shell words like --foo=bar, CC=gcc and a,b. Where an assignment may
+    // start, the scanner reads `a=1` as one first. A lone [ or ] is the test
+    // builtin, as in `[ -f x ]`.
+    bare_word: $ => token(choice(
+      /[a-zA-Z_][a-zA-Z0-9_\-\.\/,:@%^~=]*/,
+      /[0-9\-\.\/,:%+^][a-zA-Z0-9_\-\.\/,:@%+^~=]*/,
+      '[',
+      ']',
+    )),
+
+    _for_iterable: $ => repeat1($.word),
+
+    // The YSH word parts without $[expr] and @(cmd)
+    _word_part: $ => choice(
+      $.bare_word,
+      alias($.identifier, $.bare_word),
+      $.string,
+      $.variable_substitution,
+      $.command_substitution,
+      $.arithmetic_expansion,
+      $.process_substitution,
+      $.brace_expansion,
+      $.tilde_expansion,
+      alias($._word_glob, $.glob_pattern),
+    ),
+  },
+});
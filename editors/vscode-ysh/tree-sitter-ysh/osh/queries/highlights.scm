@@ -0,0 +1,165 @@
+; Tree-sitter highlighting queries for OSH
+; The shell subset of ../../queries/highlights.scm, without YSH expressions
+
+; =============================================================================
+; Comments
+; =============================================================================
+
+(comment) @comment
+
+; =============================================================================
+; Keywords
+; =============================================================================
+
+[
+  "if"
+  "then"
+  "else"
+  "elif"
+  "fi"
+  "for"
+  "in"
+  "do"
+  "done"
+  "while"
+  "until"
+  "case"
+  "esac"
+  "break"
+  "continue"
+  "return"
+  "exit"
+] @keyword.control
+
+"function" @keyword.function
+
+[
+  "declare"
+  "typeset"
+  "local"
+  "readonly"
+  "export"
+] @keyword
+
+; =============================================================================
+; Literals
+; =============================================================================
+
+(integer) @number
+
+(single_quoted_string) @string
+(double_quoted_string) @string
+(dollar_single_quoted_string) @string.special
+
+(escape_sequence) @string.escape
+
+; =============================================================================
+; Variables
+; =============================================================================
+
+(identifier) @variable
+
+(simple_variable) @variable.special
+(braced_variable
+  name: (identifier) @variable.special)
+
+((simple_variable) @variable.builtin
+  (#match? @variable.builtin "^\\$[0-9@#$!?*-]$"))
+
+; =============================================================================
+; Functions and Commands
+; =============================================================================
+
+(function_definition
+  name: (identifier) @function.definition)
+
+(command_name
+  (word
+    (bare_word) @function.call))
+
+; =============================================================================
+; Tests and Arithmetic
+; =============================================================================
+
+(test_command
+  ["[[" "]]"] @punctuation.bracket)
+
+(arithmetic_command
+  ["((" "))"] @punctuation.bracket)
+
+(c_style_for_statement
+  ["((" "))"] @punctuation.bracket)
+
+(arithmetic_expansion
+  ["$((" "))"] @punctuation.special)
+
+(test_operator) @operator
+
+(regex_pattern) @string.regex
+
+(unary_test
+  "!" @operator)
+
+(binary_test
+  operator: _ @operator)
+
+(binary_expression
+  operator: _ @operator)
+
+(unary_expression
+  operator: _ @operator)
+
+(postfix_expression
+  operator: _ @operator)
+
+; =============================================================================
+; Operators
+; =============================================================================
+
+[
+  "|"
+  "|&"
+  "&&"
+  "||"
+  "&"
+  "!"
+] @operator
+
+(redirect
+  ["<" ">" ">>" "<<" "<<<" ">&" "<&" "<>" ">|" "&>" "&>>"] @operator)
+
+(file_descriptor) @number
+
+(heredoc_delimiter) @string.special
+
+(heredoc_body) @string
+
+; =============================================================================
+; Punctuation and Substitutions
+; =============================================================================
+
+[
+  "("
+  ")"
+  "{"
+  "}"
+] @punctuation.bracket
+
+[
+  ";"
+  ";;"
+] @punctuation.delimiter
+
+(command_substitution
+  ["$(" ")"] @punctuation.special)
+
+(process_substitution
+  ["<(" ">(" ")"] @punctuation.special)
+
+(brace_expansion) @string.special
+
+(tilde_expansion) @string.special
+
+(glob_pattern) @string.special
+
+(pattern) @string.special
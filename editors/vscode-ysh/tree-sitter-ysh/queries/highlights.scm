@@ -20,7 +20,6 @@
   "setglobal"
   "proc"
   "func"
-  "typed"
   "call"
 ] @keyword
 
@@ -48,8 +47,14 @@
 ; Function definition
 "function" @keyword.function
 
-; Time keyword
-"time" @keyword
+; OSH assignment builtins
+[
+  "declare"
+  "typeset"
+  "local"
+  "readonly"
+  "export"
+] @keyword
 
 ; Expression keywords
 [
@@ -68,6 +73,9 @@
 
 (integer) @number
 (float) @number.float
+(number_with_unit) @number
+
+(char_literal) @character
 
 ; Strings
 (single_quoted_string) @string
@@ -76,7 +84,8 @@
 (raw_string) @string
 (multiline_single_string) @string
 (multiline_double_string) @string
-(j_string) @string.special
+(j8_string) @string
+(multiline_j8_string) @string
 
 (escape_sequence) @string.escape
 
@@ -89,12 +98,16 @@
 ; Variable substitutions
 (simple_variable) @variable.special
 (braced_variable
-  (identifier) @variable.special)
+  name: (identifier) @variable.special)
 
 ; Special variables
 ((simple_variable) @variable.builtin
   (#match? @variable.builtin "^\\$[0-9@#$!?*-]$"))
 
+; Hay attributes: version = '3.12'
+(hay_attribute
+  name: (identifier) @property)
+
 ; =============================================================================
 ; Functions and Procedures
 ; =============================================================================
@@ -107,15 +120,15 @@
   name: (identifier) @function.definition)
 
 (function_definition
-  (identifier) @function.definition)
+  name: (identifier) @function.definition)
 
 ; Function calls
 (call_expression
-  (identifier) @function.call)
+  function: (identifier) @function.call)
 
 (call_expression
-  (attribute_expression
-    (identifier) @function.method))
+  function: (attribute_expression
+    attribute: (identifier) @function.method))
 
 ; Command names (builtins highlighted differently)
 (command_name
@@ -127,13 +140,16 @@
 ; =============================================================================
 
 (param
-  (identifier) @variable.parameter)
+  name: (identifier) @variable.parameter)
 
 (named_param
-  (identifier) @variable.parameter)
+  name: (identifier) @variable.parameter)
 
 (rest_param
-  (identifier) @variable.parameter)
+  name: (identifier) @variable.parameter)
+
+(lambda_expression
+  parameter: (identifier) @variable.parameter)
 
 ; =============================================================================
 ; Types
@@ -160,6 +176,8 @@
   "%"
   "**"
   "++"
+  "--"
+  "?"
 ] @operator
 
 ; Comparison operators
@@ -216,6 +234,9 @@
   "..="
 ] @operator
 
+; Rest parameters and spread arguments
+"..." @operator
+
 ; =============================================================================
 ; Punctuation
 ; =============================================================================
@@ -238,6 +259,7 @@
 [
   "."
   "->"
+  "=>"
 ] @punctuation.delimiter
 
 ; =============================================================================
@@ -259,6 +281,17 @@
 ; Background operator
 "&" @operator
 
+; OSH test and arithmetic commands
+(test_command
+  ["[[" "]]"] @punctuation.bracket)
+
+(arithmetic_command
+  ["((" "))"] @punctuation.bracket)
+
+(test_operator) @operator
+
+(regex_pattern) @string.regex
+
 ; =============================================================================
 ; Redirections
 ; =============================================================================
@@ -270,6 +303,8 @@
 
 (heredoc_delimiter) @string.special
 
+(heredoc_body) @string
+
 ; =============================================================================
 ; Substitutions
 ; =============================================================================
@@ -283,6 +318,24 @@
 (array_splice
   ["@" "@[" "]"] @punctuation.special)
 
+(arithmetic_expansion
+  ["$((" "))"] @punctuation.special)
+
+(process_substitution
+  ["<(" ">(" ")"] @punctuation.special)
+
+(split_command_substitution
+  ["@(" ")"] @punctuation.special)
+
+(command_literal
+  ["^(" ")"] @punctuation.special)
+
+(expression_literal
+  ["^[" "]"] @punctuation.special)
+
+(place_reference
+  "&" @operator)
+
 ; =============================================================================
 ; Eggex (Regular Expressions)
 ; =============================================================================
@@ -290,16 +343,42 @@
 (eggex
   "/" @punctuation.special)
 
-(regex_literal) @string.regex
+(regex_named_class) @constant.builtin
 
-(regex_char_class) @string.regex
+(regex_anchor) @keyword
 
 (regex_quantifier) @operator
 
-(regex_anchor) @keyword
+(regex_quantifier
+  mode: (identifier) @attribute)
+
+(regex_range
+  "-" @operator)
+
+(regex_negation
+  "!" @operator)
 
-(regex_flags
-  (identifier) @attribute)
+(regex_splice
+  "@" @punctuation.special
+  name: (identifier) @variable)
+
+(regex_capture
+  ["<" ">"] @punctuation.bracket)
+
+(regex_capture
+  ["capture" "as"] @keyword)
+
+(regex_capture
+  conversion: (identifier) @function.call)
+
+(regex_flag
+  "!" @operator)
+
+(regex_flag
+  name: (identifier) @attribute)
+
+(eggex
+  translation: (identifier) @attribute)
 
 ; =============================================================================
 ; Word Arrays
@@ -312,8 +391,7 @@
 ; Brace Expansion
 ; =============================================================================
 
-(brace_expansion
-  ["{" "}" ","] @punctuation.special)
+(brace_expansion) @string.special
 
 ; =============================================================================
 ; Tilde Expansion
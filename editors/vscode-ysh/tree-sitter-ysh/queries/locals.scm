@@ -6,19 +6,22 @@
 ; =============================================================================
 
 ; Function and procedure bodies create scopes
-(proc_definition) @scope
-(func_definition) @scope
-(function_definition) @scope
+(proc_definition) @local.scope
+(func_definition) @local.scope
+(function_definition) @local.scope
+(lambda_expression) @local.scope
+(list_comprehension) @local.scope
+(dict_comprehension) @local.scope
 
 ; Block scopes
-(brace_group) @scope
-(subshell) @scope
+(brace_group) @local.scope
+(block_argument) @local.scope
 
 ; Control flow bodies create scopes
-(if_statement) @scope
-(for_statement) @scope
-(while_statement) @scope
-(case_arm) @scope
+(if_statement) @local.scope
+(for_statement) @local.scope
+(while_statement) @local.scope
+(case_arm) @local.scope
 
 ; =============================================================================
 ; Definitions
@@ -26,48 +29,58 @@
 
 ; Variable declarations
 (var_declaration
-  (identifier) @definition.variable)
+  name: (identifier) @local.definition)
 
 (const_declaration
-  (identifier) @definition.variable)
+  name: (identifier) @local.definition)
 
 ; Function and procedure names
 (proc_definition
-  name: (identifier) @definition.function)
+  name: (identifier) @local.definition)
 
 (func_definition
-  name: (identifier) @definition.function)
+  name: (identifier) @local.definition)
 
 (function_definition
-  (identifier) @definition.function)
+  name: (identifier) @local.definition)
 
 ; Parameters are definitions within their scope
 (param
-  (identifier) @definition.parameter)
+  name: (identifier) @local.definition)
 
 (named_param
-  (identifier) @definition.parameter)
+  name: (identifier) @local.definition)
 
 (rest_param
-  (identifier) @definition.parameter)
+  name: (identifier) @local.definition)
+
+(lambda_expression
+  parameter: (identifier) @local.definition)
 
 ; For loop variables
 (for_statement
-  (identifier) @definition.variable)
+  variable: (identifier) @local.definition)
+
+; Comprehension variables
+(list_comprehension
+  variable: (identifier) @local.definition)
+
+(dict_comprehension
+  variable: (identifier) @local.definition)
 
 ; Variable assignments (shell style)
 (variable_assignment
-  (identifier) @definition.variable)
+  name: (identifier) @local.definition)
 
 ; =============================================================================
 ; References
 ; =============================================================================
 
 ; Variable references
-(identifier) @reference
+(identifier) @local.reference
 
 ; Variable substitutions reference variables
-(simple_variable) @reference
+(simple_variable) @local.reference
 (braced_variable
-  (identifier) @reference)
+  name: (identifier) @local.reference)
 
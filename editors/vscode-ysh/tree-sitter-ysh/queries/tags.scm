@@ -10,12 +10,25 @@
 
 ; Shell functions
 (function_definition
-  (identifier) @name) @definition.function
+  name: (identifier) @name) @definition.function
 
 ; Variables (global scope)
 (var_declaration
-  (identifier) @name) @definition.variable
+  name: (identifier) @name) @definition.variable
 
 (const_declaration
-  (identifier) @name) @definition.constant
+  name: (identifier) @name) @definition.constant
+
+
+; References
+
+(call_expression
+  function: (identifier) @name) @reference.call
+
+(call_expression
+  function: (attribute_expression
+    attribute: (identifier) @name)) @reference.call
 
+(command_name
+  (word
+    (bare_word) @name)) @reference.call
@@ -0,0 +1,293 @@
+//! Checks that every file in `queries/` compiles against the grammar, and
+//! that the queries capture what editors expect on the `test/corpus` inputs.
+
+use std::fs;
+use std::path::Path;
+
+use tree_sitter::{Parser, Query, QueryCursor, Tree};
+
+struct CorpusExample {
+    name: String,
+    input: String,
+}
+
+/// Reads the inputs of every example in `test/corpus/*.txt`.
+fn corpus_examples() -> Vec<CorpusExample> {
+    let corpus_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("test/corpus");
+    let mut paths: Vec<_> = fs::read_dir(corpus_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+
+    let mut examples = Vec::new();
+    for path in paths {
+        let text = fs::read_to_string(&path).unwrap();
+        let mut lines = text.lines().peekable();
+        while let Some(line) = lines.next() {
+            if !is_header_rule(line) {
+                continue;
+            }
+            let name = lines.next().unwrap().trim().to_string();
+            assert!(is_header_rule(lines.next().unwrap()));
+
+            let mut input = String::new();
+            for line in lines.by_ref() {
+                if line.starts_with("---") {
+                    break;
+                }
+                input.push_str(line);
+                input.push('\n');
+            }
+            examples.push(CorpusExample {
+                name,
+                input: input.trim().to_string() + "\n",
+            });
+
+            // Skip the expected tree up to the next example.
+            while lines.peek().is_some_and(|line| !is_header_rule(line)) {
+                lines.next();
+            }
+        }
+    }
+    examples
+}
+
+fn is_header_rule(line: &str) -> bool {
+    line.len() >= 3 && line.chars().all(|c| c == '=')
+}
+
+fn parse(source: &str) -> Tree {
+    let mut parser = Parser::new();
+    parser.set_language(&tree_sitter_ysh::language()).unwrap();
+    parser.parse(source, None).unwrap()
+}
+
+fn query(source: &str) -> Query {
+    Query::new(&tree_sitter_ysh::language(), source).unwrap()
+}
+
+/// `(capture name, captured text)` pairs from running `query` on `source`.
+fn captures(query: &Query, source: &str) -> Vec<(String, String)> {
+    let tree = parse(source);
+    let mut cursor = QueryCursor::new();
+    let mut result = Vec::new();
+    for m in cursor.matches(query, tree.root_node(), source.as_bytes()) {
+        for capture in m.captures {
+            let name = query.capture_names()[capture.index as usize].to_string();
+            let text = capture.node.utf8_text(source.as_bytes()).unwrap();
+            result.push((name, text.to_string()));
+        }
+    }
+    result
+}
+
+fn example(name: &str) -> String {
+    corpus_examples()
+        .into_iter()
+        .find(|example| example.name == name)
+        .unwrap_or_else(|| panic!("no corpus example named {name:?}"))
+        .input
+}
+
+fn assert_captured(captures: &[(String, String)], capture: &str, text: &str) {
+    assert!(
+        captures.iter().any(|(c, t)| c == capture && t == text),
+        "expected @{capture} on {text:?}, got {captures:?}"
+    );
+}
+
+#[test]
+fn test_query_files_compile() {
+    let queries_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("queries");
+    let mut count = 0;
+    for entry in fs::read_dir(queries_dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|ext| ext == "scm") {
+            let source = fs::read_to_string(&path).unwrap();
+            if let Err(e) = Query::new(&tree_sitter_ysh::language(), &source) {
+                panic!("{} does not compile: {e}", path.display());
+            }
+            count += 1;
+        }
+    }
+    assert!(count > 0, "no query files found");
+}
+
+#[test]
+fn test_exported_queries_compile() {
+    query(tree_sitter_ysh::HIGHLIGHTS_QUERY);
+    query(tree_sitter_ysh::LOCALS_QUERY);
+    query(tree_sitter_ysh::TAGS_QUERY);
+    query(tree_sitter_ysh::INJECTIONS_QUERY);
+}
+
+#[test]
+fn test_queries_run_on_corpus() {
+    let queries = [
+        query(tree_sitter_ysh::HIGHLIGHTS_QUERY),
+        query(tree_sitter_ysh::LOCALS_QUERY),
+        query(tree_sitter_ysh::TAGS_QUERY),
+        query(tree_sitter_ysh::INJECTIONS_QUERY),
+    ];
+    let examples = corpus_examples();
+    assert!(!examples.is_empty());
+    for example in &examples {
+        for query in &queries {
+            // Every capture must land on real text, never on a missing node.
+            let tree = parse(&example.input);
+            let mut cursor = QueryCursor::new();
+            for m in cursor.matches(query, tree.root_node(), example.input.as_bytes()) {
+                for capture in m.captures {
+                    assert!(!capture.node.is_missing(), "in {:?}", example.name);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_highlights() {
+    let highlights = query(tree_sitter_ysh::HIGHLIGHTS_QUERY);
+
+    let captures = captures(&highlights, &example("Procedure definition"));
+    assert_captured(&captures, "keyword", "proc");
+    assert_captured(&captures, "function.definition", "greet");
+    assert_captured(&captures, "variable.parameter", "name");
+    assert_captured(&captures, "function.call", "echo");
+
+    let captures = self::captures(&highlights, &example("If statement (YSH style)"));
+    assert_captured(&captures, "keyword.control", "if");
+    assert_captured(&captures, "number", "0");
+
+    let captures = self::captures(&highlights, &example("Variable with type annotation"));
+    assert_captured(&captures, "keyword", "var");
+    assert_captured(&captures, "type", "Str");
+
+    let captures = self::captures(
+        &highlights,
+        &example("Dollar single quoted string with escapes"),
+    );
+    assert_captured(&captures, "string.escape", "\\n");
+
+    let captures = self::captures(&highlights, &example("J8 strings"));
+    assert_captured(&captures, "string", "b'raw'");
+    assert_captured(&captures, "string.escape", "\\yff");
+
+    let captures = self::captures(&highlights, &example("Proc and func docstrings"));
+    assert_captured(&captures, "comment.documentation", "### Add one");
+    assert_captured(&captures, "comment", "# not a doc");
+
+    let captures = self::captures(&highlights, &example("Double bracket tests"));
+    assert_captured(&captures, "operator", "-f");
+    assert_captured(&captures, "punctuation.bracket", "[[");
+
+    let captures = self::captures(
+        &highlights,
+        &example("Array assignments and declaration builtins"),
+    );
+    assert_captured(&captures, "keyword", "local");
+
+    let captures = self::captures(&highlights, &example("Function definition"));
+    assert_captured(&captures, "keyword.control", "return");
+
+    let captures = self::captures(&highlights, &example("Char literals"));
+    assert_captured(&captures, "character", "\\u{3bc}");
+
+    let captures = self::captures(&highlights, &example("Numbers with units"));
+    assert_captured(&captures, "number", "100 M");
+
+    let captures = self::captures(&highlights, &example("Func with return type"));
+    assert_captured(&captures, "type", "List");
+    assert_captured(&captures, "punctuation.delimiter", "=>");
+
+    let captures = self::captures(
+        &highlights,
+        &example("Case statement with expression, eggex and else arms"),
+    );
+    assert_captured(&captures, "keyword.control", "else");
+    assert_captured(&captures, "number", "2");
+
+    let captures = self::captures(&highlights, &example("Method call chains"));
+    assert_captured(&captures, "punctuation.delimiter", "=>");
+    assert_captured(&captures, "function.method", "upper");
+
+    let captures = self::captures(&highlights, &example("Expression and command literals"));
+    assert_captured(&captures, "punctuation.special", "^[");
+    assert_captured(&captures, "punctuation.special", "^(");
+
+    let captures = self::captures(&highlights, &example("Heredoc with substitutions"));
+    assert_captured(&captures, "string.special", "EOF");
+    assert_captured(&captures, "variable.special", "$name");
+
+    let captures = self::captures(&highlights, &example("Eggex captures"));
+    assert_captured(&captures, "constant.builtin", "d");
+    assert_captured(&captures, "keyword", "capture");
+    assert_captured(&captures, "function.call", "int");
+
+    let captures = self::captures(
+        &highlights,
+        &example("Eggex alternation, repeats and flags"),
+    );
+    assert_captured(&captures, "attribute", "i");
+    assert_captured(&captures, "attribute", "ERE");
+
+    let captures = self::captures(&highlights, &example("Lambda expressions"));
+    assert_captured(&captures, "variable.parameter", "y");
+
+    let captures = self::captures(&highlights, &example("Hay definitions and blocks"));
+    assert_captured(&captures, "property", "version");
+    assert_captured(&captures, "function.call", "TASK");
+}
+
+#[test]
+fn test_locals() {
+    let locals = query(tree_sitter_ysh::LOCALS_QUERY);
+
+    let captures = captures(&locals, &example("Proc with multiple parameters"));
+    assert_captured(&captures, "local.definition", "copy");
+    assert_captured(&captures, "local.definition", "src");
+    assert_captured(&captures, "local.definition", "force");
+
+    let captures = self::captures(&locals, &example("Proc with all parameter groups"));
+    assert_captured(&captures, "local.definition", "words");
+    assert_captured(&captures, "local.definition", "opts");
+    assert_captured(&captures, "local.definition", "block");
+
+    let captures = self::captures(&locals, &example("For loop"));
+    assert_captured(&captures, "local.definition", "item");
+
+    let captures = self::captures(&locals, &example("Dict comprehension"));
+    assert_captured(&captures, "local.definition", "k");
+    assert_captured(&captures, "local.definition", "v");
+
+    let captures = self::captures(&locals, &example("For loop with index"));
+    assert_captured(&captures, "local.definition", "i");
+    assert_captured(&captures, "local.definition", "item");
+
+    let captures = self::captures(&locals, &example("Multiple variable declaration"));
+    assert_captured(&captures, "local.definition", "a");
+    assert_captured(&captures, "local.definition", "b");
+
+    let captures = self::captures(&locals, &example("Variable declaration"));
+    assert_captured(&captures, "local.definition", "x");
+}
+
+#[test]
+fn test_tags() {
+    let tags = query(tree_sitter_ysh::TAGS_QUERY);
+
+    let captures = captures(&tags, &example("Procedure definition"));
+    assert_captured(&captures, "name", "greet");
+
+    let captures = self::captures(&tags, &example("Constant declaration"));
+    assert_captured(&captures, "name", "PI");
+    assert_captured(&captures, "definition.constant", "const PI = 3.14159");
+
+    let captures = self::captures(&tags, &example("Procedure definition"));
+    assert_captured(&captures, "reference.call", "echo");
+
+    let captures = self::captures(&tags, &example("Multiple variable declaration"));
+    assert_captured(&captures, "name", "a");
+    assert_captured(&captures, "name", "b");
+}